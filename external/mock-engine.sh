@@ -0,0 +1,56 @@
+#!/bin/sh
+# Stands in for a CLI-only engine, to try the external runner without installing one. It knows
+# single line `console.log("...")` calls, `throw` and `while (true)`, everything else is ignored.
+#
+#   mock-engine.sh [--iterations N] <script | ->
+#
+# With `--iterations` the script is run N times and `@sample <ns>` is printed after every run,
+# like an engine configured with `timings = "output"`. `-` reads the script from stdin.
+set -eu
+
+iterations=
+if [ "${1:-}" = "--iterations" ]; then
+    iterations=$2
+    shift 2
+fi
+
+script=${1:?usage: mock-engine.sh [--iterations N] <script | ->}
+if [ "$script" = "-" ]; then
+    source=$(cat)
+else
+    source=$(cat "$script")
+fi
+
+run() {
+    printf '%s\n' "$source" | while IFS= read -r line; do
+        line=$(printf '%s' "$line" | sed 's/^[[:space:]]*//')
+
+        case $line in
+            //*) ;;
+            "while (true)"* | "while(true)"*)
+                while :; do :; done
+                ;;
+            throw*)
+                printf 'Uncaught %s\n' "$(printf '%s' "$line" | sed 's/^throw[[:space:]]*//; s/;$//')" >&2
+                exit 1
+                ;;
+            console.log\(*)
+                printf '%s\n' "$line" |
+                    sed 's/^console\.log(\(.*\));\{0,1\}$/\1/; s/)$//; s/"[[:space:]]*,[[:space:]]*"/ /g; s/^"//; s/"$//'
+                ;;
+        esac
+    done
+}
+
+if [ -z "$iterations" ]; then
+    run
+    exit
+fi
+
+i=0
+while [ "$i" -lt "$iterations" ]; do
+    start=$(date +%s%N)
+    run
+    printf '@sample %s\n' "$(($(date +%s%N) - start))"
+    i=$((i + 1))
+done
@@ -0,0 +1,22 @@
+use std::sync::Mutex;
+
+/// Console output of the script. Every engine runs in its own child process,
+/// so one buffer per process is enough
+static OUTPUT: Mutex<String> = Mutex::new(String::new());
+
+/// Appends a line written by `console.log` & co.
+pub(crate) fn log(line: &str) {
+    let mut out = OUTPUT.lock().unwrap();
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Appends output that isn't split into lines yet, like the stdout of a WASI module
+pub(crate) fn write(text: &str) {
+    OUTPUT.lock().unwrap().push_str(text);
+}
+
+/// Takes everything that was written since the last call
+pub(crate) fn take() -> String {
+    std::mem::take(&mut *OUTPUT.lock().unwrap())
+}
@@ -1,6 +1,30 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::io::Error as IoError;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// An exception thrown by the script
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptError {
+    pub message: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub stack: Option<String>,
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, " at {}:{}", line, column),
+            (Some(line), None) => write!(f, " at line {}", line),
+            _ => Ok(()),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum TestError {
@@ -12,6 +36,23 @@ pub enum TestError {
     String(String),
     Other(Box<dyn Error>),
     FeatureNotEnabled(&'static str),
+    Manifest(String),
+    /// The `.wat` file couldn't be assembled, the message points at the line
+    Wat(String),
+    /// The script threw an exception
+    Script(ScriptError),
+    /// The engine panicked or its process died unexpectedly
+    Crash(String),
+    /// The engine process was terminated by a signal
+    Signal(i32),
+    /// The engine didn't finish in time and was killed
+    Timeout(Duration),
+    /// The engine hit the address space limit
+    OutOfMemory,
+    /// The engine used up its CPU time limit
+    CpuLimit,
+    /// The engine can't run the test at all, it is left out instead of failing
+    Unsupported(String),
 }
 
 impl Display for TestError {
@@ -23,6 +64,15 @@ impl Display for TestError {
             TestError::AlreadyInitialized => write!(f, "V8 is already initialized"),
             TestError::Other(err) => write!(f, "{}", err),
             TestError::String(err) => write!(f, "{}", err),
+            TestError::Manifest(err) => write!(f, "Invalid test manifest {}", err),
+            TestError::Wat(err) => write!(f, "Invalid WebAssembly text: {}", err),
+            TestError::Script(err) => write!(f, "Script error: {}", err),
+            TestError::Crash(err) => write!(f, "Engine crashed: {}", err),
+            TestError::Signal(signal) => write!(f, "Engine was terminated by signal {}", signal),
+            TestError::Timeout(timeout) => write!(f, "Timed out after {:?}", timeout),
+            TestError::OutOfMemory => write!(f, "Engine ran out of memory"),
+            TestError::CpuLimit => write!(f, "Engine exceeded its CPU time limit"),
+            TestError::Unsupported(err) => write!(f, "Unsupported: {}", err),
             #[allow(unreachable_patterns)]
             _ => write!(f, "Unknown error"),
         }
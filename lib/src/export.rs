@@ -0,0 +1,263 @@
+use std::io::{Read, Write};
+
+use serde::Serialize;
+
+use crate::errors::TestError;
+use crate::pairs::PairResult;
+use crate::validator::HTTPResultType;
+use crate::TestResult;
+
+/// Summary of one `TestResult`, times in microseconds, stats in nanoseconds
+#[derive(Debug, Serialize)]
+struct CsvRow {
+    path: String,
+    engine: String,
+    outcome: &'static str,
+    details: String,
+    success: bool,
+    time: u64,
+    init_time: u64,
+    context_time: u64,
+    compile_time: u64,
+    instantiate_time: u64,
+    run_time: u64,
+    cpu_time: u64,
+    samples: usize,
+    min: Option<u64>,
+    max: Option<u64>,
+    median: Option<f64>,
+    mean: Option<f64>,
+    p95: Option<u64>,
+    p99: Option<u64>,
+    stddev: Option<f64>,
+    cv: Option<f64>,
+    /// Peak memory in bytes
+    peak_mem: Option<u64>,
+    /// Peak CPU usage in percent
+    peak_cpu: Option<f32>,
+    console_valid: Option<bool>,
+    files_valid: Option<bool>,
+    http_valid: Option<bool>,
+}
+
+impl From<&TestResult> for CsvRow {
+    fn from(res: &TestResult) -> Self {
+        let stats = res.stats.as_ref();
+
+        CsvRow {
+            path: res.path.display().to_string(),
+            engine: res.engine.to_string(),
+            outcome: res.outcome.kind(),
+            details: res.outcome.details(),
+            success: res.success,
+            time: res.time,
+            init_time: res.init_time,
+            context_time: res.context_time,
+            compile_time: res.compile_time,
+            instantiate_time: res.instantiate_time,
+            run_time: res.run_time,
+            cpu_time: res.cpu_time,
+            samples: res.samples.len(),
+            min: stats.map(|s| s.min),
+            max: stats.map(|s| s.max),
+            median: stats.map(|s| s.median),
+            mean: stats.map(|s| s.mean),
+            p95: stats.map(|s| s.p95),
+            p99: stats.map(|s| s.p99),
+            stddev: stats.map(|s| s.stddev),
+            cv: stats.map(|s| s.cv),
+            peak_mem: res.resources.iter().map(|r| r.mem).max(),
+            peak_cpu: res.resources.iter().map(|r| r.cpu).reduce(f32::max),
+            console_valid: res.console_valid,
+            files_valid: res.files_valid,
+            http_valid: res.http.as_ref().map(|http| {
+                http.iter()
+                    .all(|http| !matches!(http.result, HTTPResultType::Fail))
+            }),
+        }
+    }
+}
+
+/// JSON with every detail, including the samples and the resource timeline
+pub fn write_json(results: &[TestResult], writer: impl Write) -> Result<(), TestError> {
+    serde_json::to_writer_pretty(writer, results).map_err(|err| TestError::Other(Box::new(err)))
+}
+
+/// CSV with one summary row per engine and test
+pub fn write_csv(results: &[TestResult], writer: impl Write) -> Result<(), TestError> {
+    let mut csv = csv::Writer::from_writer(writer);
+
+    for res in results {
+        csv.serialize(CsvRow::from(res))
+            .map_err(|err| TestError::Other(Box::new(err)))?;
+    }
+
+    csv.flush()?;
+
+    Ok(())
+}
+
+/// Reads results back that were written with `write_json`
+pub fn read_json(reader: impl Read) -> Result<Vec<TestResult>, TestError> {
+    serde_json::from_reader(reader).map_err(|err| TestError::Other(Box::new(err)))
+}
+
+/// Aligned table for humans, times are the median of the samples
+pub fn write_table(results: &[TestResult], writer: impl Write) -> Result<(), TestError> {
+    let header = [
+        "test", "engine", "outcome", "median", "mean", "p95", "cv", "cpu", "peak mem",
+    ];
+
+    let rows = results
+        .iter()
+        .map(|res| {
+            let stats = res.stats.as_ref();
+
+            vec![
+                res.path.display().to_string(),
+                res.engine.to_string(),
+                res.outcome.kind().to_string(),
+                stats.map_or("-".to_string(), |s| format_ns(s.median)),
+                stats.map_or("-".to_string(), |s| format_ns(s.mean)),
+                stats.map_or("-".to_string(), |s| format_ns(s.p95 as f64)),
+                stats.map_or("-".to_string(), |s| format!("{:.1}%", s.cv * 100.0)),
+                format_ns(res.cpu_time as f64 * 1000.0),
+                res.resources
+                    .iter()
+                    .map(|r| r.mem)
+                    .max()
+                    .map_or("-".to_string(), format_bytes),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    write_aligned(&header, &rows, writer)
+}
+
+/// One row per engine combination of a JS/wasm pair, medians in nanoseconds
+#[derive(Debug, Serialize)]
+struct PairCsvRow {
+    js: String,
+    wasm: String,
+    js_engine: String,
+    wasm_engine: String,
+    js_median: Option<f64>,
+    wasm_median: Option<f64>,
+    ratio: Option<f64>,
+    console_valid: Option<bool>,
+}
+
+/// JSON of the pairs, the results of the runs aren't included
+pub fn write_pairs_json(pairs: &[PairResult], writer: impl Write) -> Result<(), TestError> {
+    serde_json::to_writer_pretty(writer, pairs).map_err(|err| TestError::Other(Box::new(err)))
+}
+
+/// CSV with one row per engine combination of every pair
+pub fn write_pairs_csv(pairs: &[PairResult], writer: impl Write) -> Result<(), TestError> {
+    let mut csv = csv::Writer::from_writer(writer);
+
+    for pair in pairs {
+        for ratio in &pair.ratios {
+            let row = PairCsvRow {
+                js: pair.js.display().to_string(),
+                wasm: pair.wasm.display().to_string(),
+                js_engine: ratio.js_engine.to_string(),
+                wasm_engine: ratio.wasm_engine.to_string(),
+                js_median: ratio.js_median,
+                wasm_median: ratio.wasm_median,
+                ratio: ratio.ratio,
+                console_valid: pair.console_valid,
+            };
+
+            csv.serialize(row)
+                .map_err(|err| TestError::Other(Box::new(err)))?;
+        }
+    }
+
+    csv.flush()?;
+
+    Ok(())
+}
+
+/// Aligned table of the pairs, a ratio above 1 means the module is faster than the script
+pub fn write_pairs_table(pairs: &[PairResult], writer: impl Write) -> Result<(), TestError> {
+    let header = [
+        "test",
+        "js engine",
+        "wasm engine",
+        "js",
+        "wasm",
+        "js/wasm",
+        "console",
+    ];
+
+    let mut rows = vec![];
+    for pair in pairs {
+        let console = match pair.console_valid {
+            Some(true) => "same",
+            Some(false) => "differs",
+            None => "-",
+        };
+
+        for ratio in &pair.ratios {
+            rows.push(vec![
+                pair.js.with_extension("").display().to_string(),
+                ratio.js_engine.to_string(),
+                ratio.wasm_engine.to_string(),
+                ratio.js_median.map_or("-".to_string(), format_ns),
+                ratio.wasm_median.map_or("-".to_string(), format_ns),
+                ratio
+                    .ratio
+                    .map_or("-".to_string(), |ratio| format!("{:.2}x", ratio)),
+                console.to_string(),
+            ]);
+        }
+    }
+
+    write_aligned(&header, &rows, writer)
+}
+
+/// Writes `rows` as columns padded to the widest cell
+pub fn write_aligned(
+    header: &[&str],
+    rows: &[Vec<String>],
+    mut writer: impl Write,
+) -> Result<(), TestError> {
+    let mut widths = header.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        writeln!(writer, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+/// Formats nanoseconds with a fitting unit
+pub fn format_ns(ns: f64) -> String {
+    if ns >= 1e9 {
+        format!("{:.2} s", ns / 1e9)
+    } else if ns >= 1e6 {
+        format!("{:.2} ms", ns / 1e6)
+    } else if ns >= 1e3 {
+        format!("{:.2} µs", ns / 1e3)
+    } else {
+        format!("{:.0} ns", ns)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
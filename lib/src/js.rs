@@ -1,13 +1,19 @@
 use std::path::Path;
 
+use serde::{Deserialize, Serialize};
+
 use crate::errors::TestError;
 use crate::validator::Validator;
 use crate::TestResult;
 
-// pub(crate) mod chakra;
+pub(crate) mod boa;
+#[cfg(feature = "chakra")]
+pub(crate) mod chakra;
 pub(crate) mod deno;
 pub(crate) mod duktape;
+pub mod external;
 pub(crate) mod javascriptcore;
+pub(crate) mod quickjs;
 
 #[cfg(feature = "mozjs")]
 pub(crate) mod spidermonkey;
@@ -16,24 +22,28 @@ mod runner;
 #[cfg(feature = "v8")]
 pub(crate) mod v8;
 
-#[derive(Debug, Clone)]
+/// Not feature gated, results of workers built with other features have to be readable too
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum JSEngine {
-    #[cfg(feature = "v8")]
     V8,
     //https://v8.dev/
-    #[cfg(feature = "mozjs")]
     SpiderMonkey,
     //https://spidermonkey.dev/
     JavaScriptCore,
     //https://developer.apple.com/documentation/javascriptcore
     Deno,
     //https://github.com/denoland/deno
-    // Chakra,         //https://github.com/chakra-core/ChakraCore
+    Chakra,
+    //https://github.com/chakra-core/ChakraCore
     Duktape, //https://github.com/svaarala/duktape
+    Boa,     //https://github.com/boa-dev/boa
+    QuickJS, //https://bellard.org/quickjs/
              // Hermes, //https://github.com/facebook/hermes
              // JerryScript, //https://github.com/jerryscript-project/jerryscript
              // MuJS, //https://github.com/ccxvii/mujs NOTE: hmm, seems like a very small project
              // Espruino, //https://github.com/espruino/Espruino
+    /// An engine run through its command line, see `external::ExternalEngine`
+    External(String),
 }
 
 pub trait JSRunner {
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use boa_engine::object::ObjectInitializer;
+use boa_engine::property::Attribute;
+use boa_engine::{Context, JsError, JsResult, JsValue, NativeFunction, Script, Source};
+
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run, PhaseTimer};
+use crate::js::{JSEngine, JSRunner};
+use crate::validator::Validator;
+use crate::{Engine, TestResult};
+
+/// Boa, an engine written in Rust that interprets its own bytecode. Its runtime limits count loop
+/// iterations and recursion depth rather than time, so they are left at their defaults.
+pub struct Boa;
+
+impl Boa {
+    pub fn new() -> Self {
+        Boa
+    }
+
+    fn register_console(context: &mut Context) -> JsResult<()> {
+        let console = ObjectInitializer::new(context)
+            .function(NativeFunction::from_fn_ptr(Self::log), "log", 0)
+            .function(NativeFunction::from_fn_ptr(Self::log), "warn", 0)
+            .function(NativeFunction::from_fn_ptr(Self::log), "error", 0)
+            .function(NativeFunction::from_fn_ptr(Self::log), "info", 0)
+            .build();
+
+        context.register_global_property("console", console, Attribute::all())
+    }
+
+    fn log(_this: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+        let mut out = String::new();
+
+        for arg in args {
+            out.push_str(&arg.to_string(context)?.to_std_string_escaped());
+            out.push(' ');
+        }
+        out.pop();
+        console::log(&out);
+
+        Ok(JsValue::undefined())
+    }
+
+    /// Boa only keeps positions for syntax errors, where they are part of the message
+    fn script_error(err: JsError, context: &mut Context) -> ScriptError {
+        let message = match err.try_native(context) {
+            Ok(native) => native.to_string(),
+            Err(_) => err.to_string(),
+        };
+
+        ScriptError {
+            message,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Boa {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JSRunner for Boa {
+    fn run_js_file<'a>(
+        &'a mut self,
+        path: &Path,
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run(
+            path,
+            validator,
+            Engine::JS(JSEngine::Boa),
+            |(file, runs)| {
+                let mut timer = PhaseTimer::start();
+
+                // the context is the whole engine, with its realm and intrinsics
+                let mut context = Context::default();
+                timer.init();
+
+                Self::register_console(&mut context)
+                    .map_err(|err| Self::script_error(err, &mut context))?;
+                timer.context();
+
+                let script = Script::parse(Source::from_bytes(&file), None, &mut context)
+                    .and_then(|script| script.codeblock(&mut context).map(|_| script))
+                    .map_err(|err| Self::script_error(err, &mut context))?;
+                timer.compile();
+
+                repeat(runs, timer, || {
+                    script
+                        .evaluate(&mut context)
+                        .map(|_| ())
+                        .map_err(|err| Self::script_error(err, &mut context))
+                })
+            },
+        )
+    }
+}
@@ -1,16 +1,71 @@
 use std::path::Path;
-use crate::errors::TestError;
+
+use chakracore::context::ContextGuard;
+use chakracore::error::{Error, ErrorKind};
+use chakracore::value::function::CallbackInfo;
+use chakracore::value::{self, Function, Object};
+use chakracore::{script, Context, Property, Runtime};
+
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run, ChildError, PhaseTimer};
 use crate::js::{JSEngine, JSRunner};
-use crate::js::runner::run;
-use crate::{Engine, TestResult};
 use crate::validator::Validator;
+use crate::{Engine, TestResult};
 
+/// ChakraCore, the engine of the old Edge. Its JSRT API only hands out the message of an
+/// exception, so errors have no position.
 pub struct Chakra;
 
 impl Chakra {
     pub fn new() -> Self {
         Chakra
     }
+
+    fn register_console(guard: &ContextGuard) {
+        let console = Object::new(guard);
+
+        for name in ["log", "warn", "error", "info"] {
+            let log = Function::new(
+                guard,
+                Box::new(|guard: &ContextGuard, info: CallbackInfo| {
+                    let args = info
+                        .arguments
+                        .iter()
+                        .map(|arg| arg.to_string(guard))
+                        .collect::<Vec<_>>();
+                    console::log(&args.join(" "));
+
+                    Ok(value::undefined(guard))
+                }),
+            );
+
+            console.set(guard, &Property::new(guard, name), &log.into());
+        }
+
+        guard
+            .global()
+            .set(guard, &Property::new(guard, "console"), &console.into());
+    }
+
+    fn script_error(err: Error) -> ScriptError {
+        let message = match err.kind() {
+            ErrorKind::ScriptException(message) | ErrorKind::ScriptCompile(message) => {
+                message.clone()
+            }
+            _ => err.to_string(),
+        };
+
+        ScriptError {
+            message,
+            ..Default::default()
+        }
+    }
+
+    /// Setting up the runtime or context failed, that's no fault of the script
+    fn engine_error(err: Error) -> ChildError {
+        ChildError::Engine(format!("ChakraCore failed: {}", err))
+    }
 }
 
 impl Default for Chakra {
@@ -19,24 +74,39 @@ impl Default for Chakra {
     }
 }
 
-
 impl JSRunner for Chakra {
     fn run_js_file<'a>(
         &'a mut self,
         path: &Path,
         validator: &'a Validator,
     ) -> Result<TestResult, TestError> {
-        run(path,
+        run(
+            path,
             validator,
             Engine::JS(JSEngine::Chakra),
-            |(file, reruns)| {
-                let mut runtime = chakracore::Runtime::new().unwrap();
-                let context = chakracore::Context::new(&mut runtime).unwrap();
-                let guard = context.make_current().unwrap();
-
-                for _ in 0..reruns {
-                    let _ = chakracore::script::eval(&guard, &file).unwrap();
-                }
-            })
+            |(file, runs)| {
+                let mut timer = PhaseTimer::start();
+
+                let runtime = Runtime::new().map_err(Self::engine_error)?;
+                timer.init();
+
+                let context = Context::new(&runtime).map_err(Self::engine_error)?;
+                let guard = context.make_current().map_err(Self::engine_error)?;
+
+                Self::register_console(&guard);
+                timer.context();
+
+                // the script is parsed into a function that runs its global code
+                let function = script::parse(&guard, &file).map_err(Self::script_error)?;
+                timer.compile();
+
+                repeat(runs, timer, || {
+                    function
+                        .call(&guard, &[])
+                        .map(|_| ())
+                        .map_err(Self::script_error)
+                })
+            },
+        )
     }
-}
\ No newline at end of file
+}
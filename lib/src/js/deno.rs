@@ -1,18 +1,77 @@
-use deno_core::{op2, JsRuntime, ModuleCode};
+use deno_core::error::{AnyError, JsError};
+use deno_core::v8::{self, HandleScope, TryCatch};
+use deno_core::{extension, op2, JsRuntime, ModuleCode, RuntimeOptions};
 use std::path::Path;
 
-use crate::errors::TestError;
-use crate::js::runner::run;
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run, PhaseTimer};
 use crate::js::{JSEngine, JSRunner};
 use crate::validator::Validator;
 use crate::{Engine, TestResult};
 
 pub struct Deno;
 
+const CONSOLE: &str = r#"
+((ops) => {
+    const log = (...args) => ops.op_console_log(args.map(String).join(" "));
+    globalThis.console = { log, warn: log, error: log, info: log };
+})(Deno.core.ops);
+"#;
+
+#[op2(fast)]
+fn op_console_log(#[string] line: &str) {
+    console::log(line);
+}
+
+extension!(console_ext, ops = [op_console_log]);
+
+pub(crate) fn script_error(err: AnyError) -> ScriptError {
+    let Some(err) = err.downcast_ref::<JsError>() else {
+        return ScriptError {
+            message: err.to_string(),
+            ..Default::default()
+        };
+    };
+
+    let frame = err.frames.first();
+
+    ScriptError {
+        message: err.exception_message.clone(),
+        line: frame
+            .and_then(|frame| frame.line_number)
+            .map(|line| line as u32),
+        column: frame
+            .and_then(|frame| frame.column_number)
+            .map(|column| column as u32),
+        stack: err.stack.clone(),
+    }
+}
+
+/// The exception caught by `tc`, read the way deno reports errors of scripts it runs itself
+pub(crate) fn caught(tc: &mut TryCatch<HandleScope>) -> ScriptError {
+    let Some(exception) = tc.exception() else {
+        return ScriptError {
+            message: "Unknown error (uncatchable exception)".to_string(),
+            ..Default::default()
+        };
+    };
+
+    script_error(JsError::from_v8_exception(tc, exception).into())
+}
+
 impl Deno {
     pub fn new() -> Self {
         Deno
     }
+
+    /// Creates the isolate and the main realm's context, `console` isn't set up yet
+    pub(crate) fn runtime() -> JsRuntime {
+        JsRuntime::new(RuntimeOptions {
+            extensions: vec![console_ext::init_ops()],
+            ..Default::default()
+        })
+    }
 }
 impl Default for Deno {
     fn default() -> Self {
@@ -30,12 +89,30 @@ impl JSRunner for Deno {
             path,
             validator,
             Engine::JS(JSEngine::Deno),
-            |(file, reruns)| {
-                let mut runtime = JsRuntime::new(Default::default());
-                for _ in 0..reruns {
-                    let code = ModuleCode::from(file.clone());
-                    let _ = runtime.execute_script("test", code).unwrap();
-                }
+            |(file, runs)| {
+                let mut timer = PhaseTimer::start();
+
+                let mut runtime = Self::runtime();
+                timer.init();
+
+                runtime
+                    .execute_script("console", ModuleCode::from_static(CONSOLE))
+                    .map_err(script_error)?;
+                timer.context();
+
+                let scope = &mut runtime.handle_scope();
+                let tc = &mut TryCatch::new(scope);
+
+                let code = v8::String::new(tc, &file).unwrap();
+                let Some(script) = v8::Script::compile(tc, code, None) else {
+                    return Err(caught(tc).into());
+                };
+                timer.compile();
+
+                repeat(runs, timer, || match script.run(tc) {
+                    Some(_) => Ok(()),
+                    None => Err(caught(tc)),
+                })
             },
         )
     }
@@ -1,17 +1,56 @@
-use crate::errors::TestError;
-use crate::js::runner::run;
+use crate::errors::{ScriptError, TestError};
+use crate::console;
+use crate::js::runner::{repeat, run, PhaseTimer};
 use crate::js::{JSEngine, JSRunner};
 use crate::validator::Validator;
 use crate::{Engine, TestResult};
-use kg_js::JsEngine;
+use kg_js::{ConsoleFunc, JsEngine, JsError, JsInterop, Return};
 use std::path::Path;
 
 pub struct Duktape;
 
+/// Duktape already ships a `console`, we only need to redirect it
+#[derive(Debug)]
+struct Console;
+
+impl JsInterop for Console {
+    fn call(&mut self, _engine: &mut JsEngine, _func_name: &str) -> Result<Return, JsError> {
+        Ok(Return::Undefined)
+    }
+
+    fn console(&mut self, _func: ConsoleFunc, msg: &str) {
+        console::log(msg);
+    }
+}
+
 impl Duktape {
     pub fn new() -> Self {
         Duktape
     }
+
+    /// Pops the error thrown by a failed call off the stack
+    fn script_error(engine: &mut JsEngine) -> ScriptError {
+        let mut error = ScriptError {
+            message: engine.safe_to_lstring(-1),
+            ..Default::default()
+        };
+
+        if engine.is_object(-1) {
+            if engine.get_prop_string(-1, "lineNumber") && engine.is_number(-1) {
+                error.line = Some(engine.get_number(-1) as u32);
+            }
+            engine.pop();
+
+            if engine.get_prop_string(-1, "stack") && engine.is_string(-1) {
+                error.stack = Some(engine.get_string(-1).to_string());
+            }
+            engine.pop();
+        }
+
+        engine.pop();
+
+        error
+    }
 }
 
 impl Default for Duktape {
@@ -30,12 +69,31 @@ impl JSRunner for Duktape {
             path,
             validator,
             Engine::JS(JSEngine::Duktape),
-            |(file, reruns)| {
-                let mut engine = JsEngine::new();
+            |(file, runs)| {
+                let mut timer = PhaseTimer::start();
+
+                // the heap and its global context are created together
+                let mut engine = JsEngine::with_interop(Console);
+                timer.init();
+                timer.context();
+
+                // leaves the compiled program on the stack
+                engine.compile(&file).map_err(|err| ScriptError {
+                    message: err.into(),
+                    ..Default::default()
+                })?;
+                timer.compile();
+
+                repeat(runs, timer, || {
+                    engine.dup(-1);
+
+                    if engine.pcall(0).is_err() {
+                        return Err(Self::script_error(&mut engine));
+                    }
 
-                for _ in 0..reruns {
-                    engine.eval(&file).unwrap();
-                }
+                    engine.pop();
+                    Ok(())
+                })
             },
         )
     }
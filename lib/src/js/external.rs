@@ -0,0 +1,642 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ScriptError, TestError};
+use crate::js::{JSEngine, JSRunner};
+use crate::limits::Hook;
+use crate::resources::{ResourceMonitor, ResourceUsage};
+use crate::runner::{children_cpu_time, conclude};
+use crate::stats::Stats;
+use crate::validator::{Validator, DEFAULT_TIMEOUT};
+use crate::{Engine, TestResult, JS_ENGINES, WASM_ENGINES};
+
+/// # External engines
+/// Engines without a Rust crate are run through their command line, in place of the procspawn
+/// child. They are configured in a TOML file:
+///
+/// ```toml
+/// [[engine]]
+/// name = "hermes"
+/// command = "/opt/hermes/bin/hermes"
+/// args = ["{script}"] # default
+/// script = "path" # `{script}` is the absolute path, "stdin" pipes the script in as well,
+///                 # "arg" puts the source itself into `{script}`
+/// timings = "process" # one process per iteration, timed from spawn to exit
+///
+/// [[engine]]
+/// name = "mock"
+/// command = "external/mock-engine.sh"
+/// args = ["--iterations", "{iterations}", "{script}"]
+/// timings = "output" # one process that runs every iteration and prints the samples
+/// sample_prefix = "@sample " # default
+/// unit = "ms" # default
+/// ```
+///
+/// `args` can also use `{reruns}`, `{warmup}` and `{iterations}`, their sum. With `timings =
+/// "output"` the engine prints `<sample_prefix><duration>` after every iteration, the first
+/// `warmup` samples are dropped and the console is what it printed in the first iteration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalEngine {
+    /// Selects the engine like the name of a built-in one
+    pub name: String,
+    pub command: PathBuf,
+    #[serde(default = "default_args")]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub script: ScriptInput,
+    #[serde(default)]
+    pub timings: Timings,
+    #[serde(default = "default_sample_prefix")]
+    pub sample_prefix: String,
+    #[serde(default)]
+    pub unit: TimeUnit,
+}
+
+/// How the engine gets the script
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptInput {
+    /// `{script}` is replaced by the absolute path of the script
+    #[default]
+    Path,
+    /// The script is written to stdin, `{script}` is still its path
+    Stdin,
+    /// `{script}` is replaced by the source of the script
+    Arg,
+}
+
+/// Where the samples come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Timings {
+    /// Every iteration is a process of its own, timed from spawn to exit, startup included
+    #[default]
+    Process,
+    /// One process runs every iteration and prints how long each took
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    Ns,
+    Us,
+    /// What `performance.now()` and `Date.now()` measure in
+    #[default]
+    Ms,
+    S,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExternalConfig {
+    #[serde(default)]
+    engine: Vec<ExternalEngine>,
+}
+
+fn default_args() -> Vec<String> {
+    vec!["{script}".to_string()]
+}
+
+fn default_sample_prefix() -> String {
+    "@sample ".to_string()
+}
+
+impl TimeUnit {
+    fn nanos(&self, value: f64) -> u64 {
+        let factor = match self {
+            TimeUnit::Ns => 1.0,
+            TimeUnit::Us => 1e3,
+            TimeUnit::Ms => 1e6,
+            TimeUnit::S => 1e9,
+        };
+
+        (value * factor) as u64
+    }
+}
+
+impl ExternalEngine {
+    /// Reads the engines of the config file at `path`
+    pub fn load(path: &Path) -> Result<Vec<ExternalEngine>, TestError> {
+        let config = fs::read_to_string(path)?;
+        let config: ExternalConfig = toml::from_str(&config)
+            .map_err(|err| TestError::String(format!("Invalid engine config {}", err)))?;
+
+        for engine in &config.engine {
+            if JS_ENGINES
+                .iter()
+                .chain(WASM_ENGINES)
+                .any(|e| e.is(&engine.name))
+            {
+                return Err(TestError::String(format!(
+                    "External engine {} has the name of a built-in engine",
+                    engine.name
+                )));
+            }
+
+            if engine.script == ScriptInput::Arg
+                && !engine.args.iter().any(|a| a.contains("{script}"))
+            {
+                return Err(TestError::String(format!(
+                    "External engine {} takes the script as an argument, but `args` has no `{{script}}`",
+                    engine.name
+                )));
+            }
+        }
+
+        Ok(config.engine)
+    }
+
+    /// Whether the engine is called `name`
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    fn args(&self, script: &str, reruns: u32, warmup: u32) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                arg.replace("{script}", script)
+                    .replace("{reruns}", &reruns.to_string())
+                    .replace("{warmup}", &warmup.to_string())
+                    .replace("{iterations}", &(reruns + warmup).to_string())
+            })
+            .collect()
+    }
+}
+
+/// An engine process that has exited
+struct Exit {
+    status: ExitStatus,
+    /// From spawning the process until it exited
+    elapsed: Duration,
+    stdout: String,
+    stderr: String,
+}
+
+impl Exit {
+    fn error(&self) -> Option<TestError> {
+        if self.status.success() {
+            return None;
+        }
+
+        if let Some(signal) = self.status.signal() {
+            return Some(TestError::Signal(signal));
+        }
+
+        // CLI engines print uncaught exceptions to stderr and exit with an error code
+        let message = match self.stderr.trim() {
+            "" => format!(
+                "exited with code {}",
+                self.status.code().unwrap_or_default()
+            ),
+            stderr => stderr.to_string(),
+        };
+
+        Some(TestError::Script(ScriptError {
+            message,
+            ..Default::default()
+        }))
+    }
+}
+
+/// Threads feeding and draining the pipes of an engine process, it blocks once a pipe is full
+struct Pipes {
+    stdout: Option<JoinHandle<String>>,
+    stderr: Option<JoinHandle<String>>,
+}
+
+impl Pipes {
+    fn attach(child: &mut Child, source: &str) -> Self {
+        if let Some(mut stdin) = child.stdin.take() {
+            let source = source.to_string();
+            // the engine may exit without reading all of it
+            thread::spawn(move || stdin.write_all(source.as_bytes()).ok());
+        }
+
+        Pipes {
+            stdout: child.stdout.take().map(read_to_string),
+            stderr: child.stderr.take().map(read_to_string),
+        }
+    }
+
+    /// stdout and stderr, once the process has closed them
+    fn join(self) -> (String, String) {
+        let join = |pipe: Option<JoinHandle<String>>| {
+            pipe.map(|pipe| pipe.join().unwrap()).unwrap_or_default()
+        };
+
+        (join(self.stdout), join(self.stderr))
+    }
+}
+
+fn read_to_string(mut pipe: impl Read + Send + 'static) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut out = Vec::new();
+        pipe.read_to_end(&mut out).ok();
+        String::from_utf8_lossy(&out).into_owned()
+    })
+}
+
+/// The `ResourceMonitor` of the process `pid`, recording on its own thread until `stop`
+struct Monitor {
+    monitor: Arc<ResourceMonitor>,
+    handle: JoinHandle<()>,
+}
+
+impl Monitor {
+    fn start(pid: u32, start: Instant) -> Self {
+        let monitor = Arc::new(ResourceMonitor::new(pid));
+
+        let handle = {
+            let monitor = Arc::clone(&monitor);
+            thread::spawn(move || monitor.start(&start))
+        };
+
+        Monitor { monitor, handle }
+    }
+
+    /// Appends what was recorded to `resources`
+    fn stop(self, resources: &mut Vec<ResourceUsage>) {
+        self.monitor.stop();
+        self.handle.join().unwrap();
+
+        resources.extend(self.monitor.resources.read().unwrap().iter().cloned());
+    }
+}
+
+/// Runs `.js` files with an `ExternalEngine`. Each process is monitored like a procspawn child,
+/// the limits are applied to it before the engine starts.
+pub struct External {
+    engine: ExternalEngine,
+}
+
+impl External {
+    pub fn new(engine: ExternalEngine) -> Self {
+        External { engine }
+    }
+
+    fn spawn(&self, script: &str, validator: &Validator) -> io::Result<Child> {
+        // a server is only started once
+        let (reruns, warmup) = if validator.http.is_empty() {
+            (validator.reruns, validator.warmup)
+        } else {
+            (1, 0)
+        };
+
+        let mut command = Command::new(&self.engine.command);
+        command
+            .args(self.engine.args(script, reruns, warmup))
+            .stdin(match self.engine.script {
+                ScriptInput::Stdin => Stdio::piped(),
+                ScriptInput::Path | ScriptInput::Arg => Stdio::null(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // anything the engine starts is killed with it, it would keep the pipes open otherwise
+            .process_group(0);
+
+        validator
+            .limits
+            .pre_exec_hook(&mut command, CommandExt::pre_exec::<Hook>);
+
+        command.spawn()
+    }
+
+    /// Runs the engine once, killing it at `deadline`, `None` then
+    fn run_process(
+        &self,
+        script: &str,
+        source: &str,
+        validator: &Validator,
+        start: Instant,
+        deadline: Instant,
+        resources: &mut Vec<ResourceUsage>,
+    ) -> Result<Option<Exit>, TestError> {
+        let spawned = Instant::now();
+        let mut child = self.spawn(script, validator)?;
+        let pid = child.id();
+
+        let monitor = Monitor::start(pid, start);
+        let pipes = Pipes::attach(&mut child, source);
+
+        let (exited, exit) = mpsc::channel();
+        thread::spawn(move || {
+            let status = child.wait();
+            exited.send((status, spawned.elapsed())).ok();
+        });
+
+        let exit = match exit.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(exit) => Some(exit),
+            Err(_) => {
+                killpg(Pid::from_raw(pid as i32), Signal::SIGKILL).ok();
+                // reaped, so its CPU time is accounted for
+                exit.recv().ok();
+                None
+            }
+        };
+
+        monitor.stop(resources);
+        let (stdout, stderr) = pipes.join();
+
+        match exit {
+            Some((status, elapsed)) => Ok(Some(Exit {
+                status: status?,
+                elapsed,
+                stdout,
+                stderr,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Splits what the engine printed into console output and samples, `Timings::Output` only.
+    /// Every iteration prints the same again, so the console is what came before the first sample.
+    /// A sample that isn't a number fails the script, like the script printing one would.
+    fn parse_output(&self, stdout: &str) -> Result<(String, Vec<u64>), TestError> {
+        let mut console = String::new();
+        let mut samples = vec![];
+
+        for line in stdout.lines() {
+            let Some(sample) = line.strip_prefix(&self.engine.sample_prefix) else {
+                if samples.is_empty() {
+                    console.push_str(line);
+                    console.push('\n');
+                }
+                continue;
+            };
+
+            let sample = sample.trim().parse::<f64>().map_err(|_| {
+                TestError::Script(ScriptError {
+                    message: format!(
+                        "{} printed an invalid sample `{}`",
+                        self.engine.name, sample
+                    ),
+                    ..Default::default()
+                })
+            })?;
+
+            samples.push(self.engine.unit.nanos(sample));
+        }
+
+        Ok((console, samples))
+    }
+}
+
+impl JSRunner for External {
+    fn run_js_file<'a>(
+        &'a mut self,
+        path: &Path,
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        if !path.is_file() {
+            return Err(TestError::IsDir);
+        }
+
+        if path.extension().and_then(|ext| ext.to_str()) != Some("js") {
+            return Err(TestError::InvalidFileType);
+        }
+
+        let source = fs::read_to_string(path)?;
+        let script = match self.engine.script {
+            ScriptInput::Arg => source.clone(),
+            ScriptInput::Path | ScriptInput::Stdin => {
+                path.canonicalize()?.to_string_lossy().into_owned()
+            }
+        };
+
+        let mut res = TestResult::new(
+            path,
+            Engine::JS(JSEngine::External(self.engine.name.clone())),
+        );
+
+        let timeout = validator.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let cpu_start = children_cpu_time();
+        let start = Instant::now();
+        let deadline = start + timeout;
+        let mut resources = vec![];
+
+        let failure = if !validator.http.is_empty() {
+            let mut child = self.spawn(&script, validator)?;
+            let pid = child.id();
+
+            let monitor = Monitor::start(pid, start);
+            let pipes = Pipes::attach(&mut child, &source);
+
+            let http_res = validator.validate_http(&monitor.monitor);
+            killpg(Pid::from_raw(pid as i32), Signal::SIGKILL).ok();
+            child.wait()?;
+            res.time = start.elapsed().as_micros() as u64;
+
+            monitor.stop(&mut resources);
+            let (stdout, _) = pipes.join();
+            res.console = stdout.lines().map(|l| l.to_string()).collect();
+
+            match http_res {
+                Ok(http_res) => {
+                    res.http = Some(http_res);
+                    None
+                }
+                Err(err) => Some(err),
+            }
+        } else {
+            let processes = match self.engine.timings {
+                Timings::Process => validator.warmup + validator.reruns,
+                Timings::Output => 1,
+            };
+
+            let mut console = None;
+            let mut failure = None;
+
+            for i in 0..processes {
+                let exit =
+                    self.run_process(&script, &source, validator, start, deadline, &mut resources)?;
+
+                let Some(exit) = exit else {
+                    failure = Some(TestError::Timeout(timeout));
+                    break;
+                };
+
+                if let Some(err) = exit.error() {
+                    failure = Some(err);
+                    break;
+                }
+
+                match self.engine.timings {
+                    Timings::Process => {
+                        if i >= validator.warmup {
+                            res.samples.push(exit.elapsed.as_nanos() as u64);
+                        }
+
+                        // every run prints the same again, only the first is kept
+                        if i == 0 {
+                            console = Some(exit.stdout);
+                        }
+                    }
+                    Timings::Output => match self.parse_output(&exit.stdout) {
+                        Ok((out, samples)) => {
+                            res.samples = samples
+                                .into_iter()
+                                .skip(validator.warmup as usize)
+                                .collect();
+                            console = Some(out);
+                        }
+                        Err(err) => failure = Some(err),
+                    },
+                }
+            }
+
+            // the engine doesn't report its phases, all of it is spent running
+            res.time = start.elapsed().as_micros() as u64;
+            res.run_time = res.time;
+
+            if let (Some(console), None) = (console, &failure) {
+                res.console = console.lines().map(|l| l.to_string()).collect();
+
+                if !validator.console.is_empty() {
+                    res.console_valid = Some(validator.validate_console(console).result);
+                }
+            }
+
+            failure
+        };
+
+        res.stats = Stats::from_samples(&res.samples);
+        res.resources = resources;
+        res.cpu_time = children_cpu_time().saturating_sub(cpu_start);
+
+        conclude(res, failure, validator, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_file;
+    use crate::Outcome;
+
+    fn mock(timings: Timings) -> ExternalEngine {
+        ExternalEngine {
+            name: "mock".to_string(),
+            command: PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../external/mock-engine.sh"
+            )),
+            args: match timings {
+                Timings::Process => default_args(),
+                Timings::Output => vec![
+                    "--iterations".to_string(),
+                    "{iterations}".to_string(),
+                    "{script}".to_string(),
+                ],
+            },
+            script: ScriptInput::Path,
+            timings,
+            sample_prefix: default_sample_prefix(),
+            unit: TimeUnit::Ns,
+        }
+    }
+
+    fn run(timings: Timings, source: &str) -> TestResult {
+        let file = temp_file(".js", source.as_bytes());
+        let validator = Validator {
+            reruns: 3,
+            warmup: 2,
+            ..Validator::new()
+        };
+
+        External::new(mock(timings))
+            .run_js_file(file.path(), &validator)
+            .unwrap()
+    }
+
+    #[test]
+    fn fills_in_the_args() {
+        let engine = ExternalEngine {
+            args: ["{script}", "-n", "{reruns}", "{warmup}", "{iterations}"]
+                .map(String::from)
+                .to_vec(),
+            ..mock(Timings::Process)
+        };
+
+        assert_eq!(
+            engine.args("/tests/a.js", 3, 2),
+            ["/tests/a.js", "-n", "3", "2", "5"]
+        );
+    }
+
+    #[test]
+    fn parses_console_and_samples() {
+        let engine = ExternalEngine {
+            unit: TimeUnit::Us,
+            ..mock(Timings::Output)
+        };
+
+        let (console, samples) = External::new(engine)
+            .parse_output("hello\n@sample 1.5\nhello\n@sample  2 \n")
+            .unwrap();
+        assert_eq!(console, "hello\n");
+        assert_eq!(samples, [1500, 2000]);
+    }
+
+    #[test]
+    fn invalid_samples_are_script_errors() {
+        let err = External::new(mock(Timings::Output))
+            .parse_output("@sample fast\n")
+            .unwrap_err();
+
+        let TestError::Script(err) = err else {
+            panic!("expected a script error, got {:?}", err);
+        };
+        assert_eq!(err.message, "mock printed an invalid sample `fast`");
+    }
+
+    #[test]
+    fn process_timings_run_a_process_per_iteration() {
+        let res = run(Timings::Process, "console.log(\"hi\");\n");
+
+        assert!(matches!(res.outcome, Outcome::Passed), "{:?}", res.outcome);
+        assert_eq!(res.samples.len(), 3);
+        assert_eq!(res.console, ["hi"]);
+    }
+
+    #[test]
+    fn output_timings_read_the_samples() {
+        let res = run(Timings::Output, "console.log(\"hi\");\n");
+
+        assert!(matches!(res.outcome, Outcome::Passed), "{:?}", res.outcome);
+        assert_eq!(res.samples.len(), 3);
+        assert_eq!(res.console, ["hi"]);
+    }
+
+    #[test]
+    fn invalid_samples_fail_the_script() {
+        let res = run(Timings::Output, "console.log(\"@sample fast\");\n");
+
+        let Outcome::ScriptError(err) = res.outcome else {
+            panic!("expected a script error, got {:?}", res.outcome);
+        };
+        assert_eq!(err.message, "mock printed an invalid sample `fast`");
+    }
+
+    #[test]
+    fn exceptions_are_script_errors() {
+        let res = run(Timings::Process, "throw new Error(\"boom\");\n");
+
+        let Outcome::ScriptError(err) = res.outcome else {
+            panic!("expected a script error, got {:?}", res.outcome);
+        };
+        assert_eq!(err.message, "Uncaught new Error(\"boom\")");
+    }
+}
@@ -1,13 +1,22 @@
 use std::path::Path;
+use std::{mem, ptr, slice};
 
-use javascriptcore::{Class, Context, ContextExt, Value, ValueExt};
+use glib::ffi::{gpointer, GPtrArray};
+use glib::gobject_ffi::G_TYPE_NONE;
+use glib::translate::{from_glib_full, from_glib_none, ToGlibPtr};
+use javascriptcore::{Context, ContextExt, ExceptionExt, Value, ValueExt};
+use javascriptcore_rs_sys as ffi;
 
-use crate::errors::TestError;
-use crate::js::runner::run;
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run, PhaseTimer};
 use crate::js::{JSEngine, JSRunner};
 use crate::validator::Validator;
 use crate::{Engine, TestResult};
 
+/// JavaScriptCore, the engine of WebKit, through the GObject API of its GTK port. That API has no
+/// compiled scripts, so the script is evaluated at global scope in every iteration and the compile
+/// phase is part of the run.
 pub struct JavaScriptCore;
 
 impl JavaScriptCore {
@@ -15,15 +24,76 @@ impl JavaScriptCore {
         JavaScriptCore
     }
 
-    // fn register_console(c: &mut Context) {
-    //
-    //     let console = Value::builder().context(c).build();
-    //
-    //     Value::is_function()
-    //
-    //     c.set_value("console", &console);
-    //
-    // }
+    /// Creates the virtual machine together with the context, `console` isn't registered yet
+    pub(crate) fn context() -> Context {
+        Context::new()
+    }
+
+    /// Takes the exception thrown by the last evaluation, if there was one
+    pub(crate) fn script_error(context: &Context) -> Option<ScriptError> {
+        let exception = context.exception()?;
+        context.clear_exception();
+
+        Some(ScriptError {
+            message: exception.to_str().to_string(),
+            line: Some(exception.line_number()),
+            column: Some(exception.column_number()),
+            stack: exception.backtrace_string().map(|s| s.to_string()),
+        })
+    }
+
+    /// The bindings leave out creating functions, so `console` is built with the C API of the
+    /// GTK port, which hands out no `JSContextRef` for `JSObjectMakeFunctionWithCallback`
+    fn register_console(context: &Context) {
+        let console: Value = unsafe {
+            from_glib_full(ffi::jsc_value_new_object(
+                context.to_glib_none().0,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ))
+        };
+
+        // a variadic function gets its arguments as a `GPtrArray` of `JSCValue`s
+        let callback = unsafe {
+            mem::transmute::<unsafe extern "C" fn(*mut GPtrArray, gpointer), unsafe extern "C" fn()>(
+                console_log,
+            )
+        };
+
+        for name in ["log", "warn", "error", "info"] {
+            let log: Value = unsafe {
+                from_glib_full(ffi::jsc_value_new_function_variadic(
+                    context.to_glib_none().0,
+                    name.to_glib_none().0,
+                    Some(callback),
+                    ptr::null_mut(),
+                    None,
+                    G_TYPE_NONE,
+                ))
+            };
+
+            console.object_set_property(name, &log);
+        }
+
+        context.set_value("console", &console);
+    }
+}
+
+/// `console.log` and friends, prints the arguments separated by spaces like V8
+unsafe extern "C" fn console_log(args: *mut GPtrArray, _user_data: gpointer) {
+    let args = match args.as_ref() {
+        Some(args) if args.len > 0 => {
+            slice::from_raw_parts(args.pdata as *const *mut ffi::JSCValue, args.len as usize)
+        }
+        _ => &[],
+    };
+
+    let args = args
+        .iter()
+        .map(|&arg| from_glib_none::<_, Value>(arg).to_str().to_string())
+        .collect::<Vec<_>>();
+
+    console::log(&args.join(" "));
 }
 
 impl Default for JavaScriptCore {
@@ -42,14 +112,26 @@ impl JSRunner for JavaScriptCore {
             path,
             validator,
             Engine::JS(JSEngine::JavaScriptCore),
-            |(file, reruns)| {
-                let context = Context::new();
+            |(file, runs)| {
+                let mut timer = PhaseTimer::start();
+
+                let context = Self::context();
+                timer.init();
+
+                Self::register_console(&context);
+                timer.context();
+
+                // `evaluate` compiles and runs in one go
+                timer.compile();
 
-                // Self::register_console(&mut context); adding functions to values is not supported in the javascriptcore-rs bindings
+                repeat(runs, timer, || {
+                    context.evaluate(&file);
 
-                for _ in 0..reruns {
-                    context.evaluate(&file).unwrap();
-                }
+                    match Self::script_error(&context) {
+                        Some(err) => Err(err),
+                        None => Ok(()),
+                    }
+                })
             },
         )
     }
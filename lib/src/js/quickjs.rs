@@ -0,0 +1,211 @@
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::time::Instant;
+
+use rquickjs::function::Rest;
+use rquickjs::loader::{Resolver, ScriptLoader};
+use rquickjs::prelude::Coerced;
+use rquickjs::{qjs, CaughtError, Context, Ctx, FromJs, Function, Module, Object, Runtime, Value};
+
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run_script, ChildError, PhaseTimer};
+use crate::js::{JSEngine, JSRunner};
+use crate::validator::Validator;
+use crate::{Engine, TestResult};
+
+/// QuickJS, a small interpreter with ES2020 support. Scripts are compiled to bytecode once, which
+/// is run in every iteration. Files with `import` or `export` are run as modules, those can only be
+/// evaluated once, so they are compiled again in every iteration. Their imports are loaded relative
+/// to them and only run once, in the first iteration.
+///
+/// The interrupt handler stops a script that runs past the timeout.
+pub struct QuickJS;
+
+/// Resolves imports relative to the importing module. `FileResolver` only handles relative paths,
+/// but the scripts are named by their absolute path.
+struct RelativeResolver;
+
+impl Resolver for RelativeResolver {
+    fn resolve<'js>(
+        &mut self,
+        _ctx: &Ctx<'js>,
+        base: &str,
+        name: &str,
+    ) -> rquickjs::Result<String> {
+        let dir = Path::new(base).parent().unwrap_or(Path::new(""));
+
+        match dir.join(name).canonicalize() {
+            Ok(path) => Ok(path.to_string_lossy().into_owned()),
+            Err(_) => Err(rquickjs::Error::new_resolving(base, name)),
+        }
+    }
+}
+
+impl QuickJS {
+    pub fn new() -> Self {
+        QuickJS
+    }
+
+    fn register_console(ctx: &Ctx) -> rquickjs::Result<()> {
+        let console = Object::new(ctx.clone())?;
+
+        let log = Function::new(ctx.clone(), |args: Rest<Coerced<String>>| {
+            let args = args.0.iter().map(|arg| arg.0.as_str()).collect::<Vec<_>>();
+            console::log(&args.join(" "));
+        })?;
+
+        console.set("log", log.clone())?;
+        console.set("warn", log.clone())?;
+        console.set("error", log.clone())?;
+        console.set("info", log)?;
+
+        ctx.globals().set("console", console)
+    }
+
+    /// Whether QuickJS would parse `source` as a module
+    fn is_module(source: &str) -> bool {
+        // a script with a NUL in it can't be run by QuickJS either
+        let Ok(source) = CString::new(source) else {
+            return false;
+        };
+
+        // SAFETY: like the tokenizer, `JS_DetectModule` stops at the NUL after `len` bytes rather
+        // than at `len`, and `source` has one
+        unsafe { qjs::JS_DetectModule(source.as_ptr(), source.as_bytes().len() as _) != 0 }
+    }
+
+    /// Compiles the script to bytecode without running it
+    fn compile<'js>(ctx: &Ctx<'js>, source: &str, name: &CStr) -> rquickjs::Result<Value<'js>> {
+        let source = CString::new(source)?;
+        let flags = qjs::JS_EVAL_TYPE_GLOBAL | qjs::JS_EVAL_FLAG_COMPILE_ONLY;
+
+        // SAFETY: `source` is NUL-terminated as `JS_Eval` requires, and the compiled function it
+        // returns is owned by us, so `Value` may free it
+        unsafe {
+            let compiled = qjs::JS_Eval(
+                ctx.as_raw().as_ptr(),
+                source.as_ptr(),
+                source.as_bytes().len() as _,
+                name.as_ptr(),
+                flags as i32,
+            );
+            if qjs::JS_IsException(compiled) {
+                return Err(rquickjs::Error::Exception);
+            }
+
+            Ok(Value::from_raw(ctx.clone(), compiled))
+        }
+    }
+
+    /// Runs bytecode from `compile`, which can be run any number of times
+    fn execute(ctx: &Ctx, compiled: &Value) -> rquickjs::Result<()> {
+        // SAFETY: `JS_EvalFunction` frees the function it runs, so it gets its own reference, and
+        // the result is ours to free
+        unsafe {
+            let ctx = ctx.as_raw().as_ptr();
+            let result = qjs::JS_EvalFunction(ctx, qjs::JS_DupValue(compiled.as_raw()));
+            if qjs::JS_IsException(result) {
+                return Err(rquickjs::Error::Exception);
+            }
+
+            qjs::JS_FreeValue(ctx, result);
+        }
+
+        Ok(())
+    }
+
+    /// Setting up the runtime or context failed, that's no fault of the script
+    fn engine_error(err: rquickjs::Error) -> ChildError {
+        ChildError::Engine(format!("QuickJS failed: {}", err))
+    }
+
+    /// Takes the pending exception, the interrupt handler throws an uncatchable one after the
+    /// deadline, that is reported as a timeout instead
+    fn error(ctx: &Ctx, err: rquickjs::Error, deadline: Instant) -> ChildError {
+        if Instant::now() >= deadline {
+            return ChildError::Timeout;
+        }
+
+        let error = match CaughtError::from_error(ctx, err) {
+            CaughtError::Exception(exception) => ScriptError {
+                message: exception.message().unwrap_or_else(|| exception.to_string()),
+                line: exception.line().and_then(|line| line.try_into().ok()),
+                column: exception.column().and_then(|column| column.try_into().ok()),
+                stack: exception.stack().filter(|stack| !stack.is_empty()),
+            },
+            CaughtError::Value(value) => ScriptError {
+                message: Coerced::<String>::from_js(ctx, value)
+                    .map(|value| value.0)
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+            CaughtError::Error(err) => ScriptError {
+                message: err.to_string(),
+                ..Default::default()
+            },
+        };
+
+        ChildError::Script(error)
+    }
+}
+
+impl Default for QuickJS {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JSRunner for QuickJS {
+    fn run_js_file<'a>(
+        &'a mut self,
+        path: &Path,
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run_script(
+            path,
+            validator,
+            Engine::JS(JSEngine::QuickJS),
+            |(script, runs)| {
+                let deadline = Instant::now() + runs.timeout;
+                let mut timer = PhaseTimer::start();
+
+                let runtime = Runtime::new().map_err(Self::engine_error)?;
+                runtime.set_interrupt_handler(Some(Box::new(move || Instant::now() >= deadline)));
+                runtime.set_loader((RelativeResolver,), (ScriptLoader::default(),));
+                timer.init();
+
+                let context = Context::full(&runtime).map_err(Self::engine_error)?;
+
+                context.with(|ctx| {
+                    Self::register_console(&ctx).map_err(|err| Self::error(&ctx, err, deadline))?;
+                    timer.context();
+
+                    let name = script.path.to_string_lossy();
+
+                    if Self::is_module(&script.source) {
+                        // `Module::evaluate` compiles and runs in one go
+                        timer.compile();
+
+                        return repeat(runs, timer, || {
+                            Module::evaluate(ctx.clone(), name.as_ref(), script.source.as_str())
+                                .map(|_| ())
+                                .map_err(|err| Self::error(&ctx, err, deadline))
+                        });
+                    }
+
+                    let compiled = CString::new(name.as_ref())
+                        .map_err(rquickjs::Error::from)
+                        .and_then(|name| Self::compile(&ctx, &script.source, &name))
+                        .map_err(|err| Self::error(&ctx, err, deadline))?;
+                    timer.compile();
+
+                    repeat(runs, timer, || {
+                        Self::execute(&ctx, &compiled)
+                            .map_err(|err| Self::error(&ctx, err, deadline))
+                    })
+                })
+            },
+        )
+    }
+}
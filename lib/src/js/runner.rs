@@ -1,67 +1,56 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
 use crate::errors::TestError;
-use crate::resources::ResourceMonitor;
+pub(super) use crate::runner::{repeat, ChildError, ChildResult, PhaseTimer, Runs};
 use crate::validator::Validator;
 use crate::{Engine, TestResult};
-use std::path::Path;
-use std::sync::Arc;
-use std::time::Instant;
-use std::{fs, thread};
 
+/// The script together with where it was read from, for engines that load the modules it imports
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Script {
+    /// Absolute, so imports resolve no matter where the child runs
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// Runs the `.js` file at `path` with `run_file` in a procspawn child
 pub(super) fn run(
     path: &Path,
     validator: &Validator,
     engine: Engine,
-    run_file: fn((String, u32)),
+    run_file: fn((String, Runs)) -> ChildResult,
 ) -> Result<TestResult, TestError> {
-    if !path.is_file() {
-        return Err(TestError::IsDir);
-    }
+    let file = read(path)?;
 
-    if path.extension().unwrap().to_str().unwrap() != "js" {
-        return Err(TestError::InvalidFileType);
-    }
-
-    let file = fs::read_to_string(path)?;
-
-    let mut res = TestResult::new(path, engine);
-    procspawn::init();
+    crate::runner::run(path, validator, engine, file, run_file, None)
+}
 
-    let reruns = if !validator.http.is_empty() {
-        1
-    } else {
-        validator.reruns
+/// Like `run`, but the child gets the path of the script as well
+pub(super) fn run_script(
+    path: &Path,
+    validator: &Validator,
+    engine: Engine,
+    run_script: fn((Script, Runs)) -> ChildResult,
+) -> Result<TestResult, TestError> {
+    let script = Script {
+        source: read(path)?,
+        path: path.canonicalize()?,
     };
 
-    let mut h = procspawn::spawn((file, reruns), run_file);
-
-    let start = Instant::now();
-    let pid = h.pid().unwrap();
-
-    let monitor = ResourceMonitor::new(pid);
-    let monitor = Arc::new(monitor);
-
-    let handle = {
-        let monitor = Arc::clone(&monitor);
-        thread::spawn(move || {
-            monitor.start(&start);
-        })
-    };
+    crate::runner::run(path, validator, engine, script, run_script, None)
+}
 
-    if !validator.http.is_empty() {
-        let monitor = Arc::clone(&monitor);
-        let http_res = validator.validate_http(&monitor)?;
-        h.kill().unwrap();
-        res.http = Some(http_res);
-    } else {
-        h.join().unwrap();
+fn read(path: &Path) -> Result<String, TestError> {
+    if !path.is_file() {
+        return Err(TestError::IsDir);
     }
 
-    let monitor = Arc::clone(&monitor);
-    monitor.stop(); //hopefully we can lock this shit, while the thread is obviously running... Else it will explode...
-
-    handle.join().unwrap();
-
-    res.resources = monitor.resources.read().unwrap().to_vec();
+    if path.extension().and_then(|ext| ext.to_str()) != Some("js") {
+        return Err(TestError::InvalidFileType);
+    }
 
-    Ok(res)
+    Ok(fs::read_to_string(path)?)
 }
@@ -1,21 +1,129 @@
+use ::std::os::raw::c_char;
 use ::std::path::Path;
 use ::std::ptr;
 
+use mozjs::conversions::jsstr_to_string;
+use mozjs::jsapi::*;
 use mozjs::jsval::UndefinedValue;
 use mozjs::rooted;
-use mozjs::rust::{RealmOptions, Runtime};
-use mozjs::rust::SIMPLE_GLOBAL_CLASS;
 use mozjs::rust::jsapi_wrapped as jsapi;
-use mozjs::jsapi::*;
+use mozjs::rust::SIMPLE_GLOBAL_CLASS;
+use mozjs::rust::{transform_str_to_source_text, CompileOptionsWrapper, RealmOptions, Runtime};
 
-use crate::{Engine, TestResult};
-use crate::errors::TestError;
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run, PhaseTimer};
 use crate::js::{JSEngine, JSRunner};
-use crate::js::runner::run;
 use crate::validator::Validator;
+use crate::{Engine, TestResult};
 
 pub struct SpiderMonkey;
 
+const CONSOLE: &str = r#"
+globalThis.console = {
+    log: __console_log,
+    warn: __console_log,
+    error: __console_log,
+    info: __console_log,
+};
+"#;
+
+unsafe extern "C" fn console_log(cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+
+    let mut out = Vec::with_capacity(argc as usize);
+
+    for i in 0..argc {
+        let arg = mozjs::rust::Handle::from_raw(args.get(i));
+        rooted!(in(cx) let arg = mozjs::rust::ToString(cx, arg));
+        out.push(jsstr_to_string(cx, arg.get()));
+    }
+
+    console::log(&out.join(" "));
+
+    args.rval().set(UndefinedValue());
+    true
+}
+
+/// Takes the pending exception off the context
+pub(crate) unsafe fn script_error(cx: *mut JSContext) -> ScriptError {
+    rooted!(in(cx) let mut exception = UndefinedValue());
+
+    if !JS_GetPendingException(cx, exception.handle_mut().into()) {
+        return ScriptError {
+            message: "Unknown error (uncatchable exception)".to_string(),
+            ..Default::default()
+        };
+    }
+    JS_ClearPendingException(cx);
+
+    rooted!(in(cx) let message = mozjs::rust::ToString(cx, exception.handle()));
+    let mut error = ScriptError {
+        message: jsstr_to_string(cx, message.get()),
+        ..Default::default()
+    };
+
+    if exception.is_object() {
+        rooted!(in(cx) let object = exception.to_object());
+        rooted!(in(cx) let mut value = UndefinedValue());
+
+        let get = |name: &[u8], value: MutableHandleValue| {
+            JS_GetProperty(
+                cx,
+                object.handle().into(),
+                name.as_ptr() as *const c_char,
+                value,
+            )
+        };
+
+        if get(b"lineNumber\0", value.handle_mut().into()) && value.is_number() {
+            error.line = Some(value.to_number() as u32);
+        }
+
+        if get(b"columnNumber\0", value.handle_mut().into()) && value.is_number() {
+            error.column = Some(value.to_number() as u32);
+        }
+
+        if get(b"stack\0", value.handle_mut().into()) && value.is_string() {
+            error.stack = Some(jsstr_to_string(cx, value.to_string()));
+        }
+    }
+
+    error
+}
+
+/// SpiderMonkey with a runtime on it. The fields drop in order, the engine outlives the runtime.
+pub(crate) struct Instance {
+    pub rt: Runtime,
+    _engine: mozjs::rust::JSEngine,
+}
+
+impl Instance {
+    pub fn new() -> Self {
+        let engine = mozjs::rust::JSEngine::init().unwrap();
+
+        Instance {
+            rt: Runtime::new(engine.handle()),
+            _engine: engine,
+        }
+    }
+
+    /// A new global object, which the caller roots and enters the realm of
+    pub fn new_global(&self) -> *mut JSObject {
+        let options = RealmOptions::default();
+
+        unsafe {
+            JS_NewGlobalObject(
+                self.rt.cx(),
+                &SIMPLE_GLOBAL_CLASS,
+                ptr::null_mut(),
+                OnNewGlobalHookOption::FireOnNewGlobalHook,
+                &*options,
+            )
+        }
+    }
+}
+
 impl SpiderMonkey {
     pub fn new() -> Self {
         SpiderMonkey
@@ -38,32 +146,50 @@ impl JSRunner for SpiderMonkey {
             path,
             validator,
             Engine::JS(JSEngine::SpiderMonkey),
-            |(file, reruns)| {
-                let engine = mozjs::rust::JSEngine::init().unwrap();
-                let rt = Runtime::new(engine.handle());
-
-                let options = RealmOptions::default();
+            |(file, runs)| {
+                let mut timer = PhaseTimer::start();
 
-                rooted!(in(rt.cx()) let global  = unsafe {
-                    JS_NewGlobalObject(rt.cx(), &SIMPLE_GLOBAL_CLASS, ptr::null_mut(),
-                        OnNewGlobalHookOption::FireOnNewGlobalHook,
-                        &*options)
-                });
+                let instance = Instance::new();
+                let rt = &instance.rt;
+                timer.init();
 
+                rooted!(in(rt.cx()) let global = instance.new_global());
+                let _ac = JSAutoRealm::new(rt.cx(), global.get());
 
                 rooted!(in(rt.cx()) let mut rval = UndefinedValue());
 
-
-                for _ in 0..reruns {
-                    let _ = rt.evaluate_script(
-                        global.handle(),
-                        &file,
-                        "inline.js",
-                        1,
-                        rval.handle_mut(),
+                unsafe {
+                    JS_DefineFunction(
+                        rt.cx(),
+                        global.handle().into(),
+                        b"__console_log\0".as_ptr() as *const c_char,
+                        Some(console_log),
+                        0,
+                        0,
                     );
                 }
+
+                rt.evaluate_script(global.handle(), CONSOLE, "console.js", 1, rval.handle_mut())
+                    .map_err(|_| unsafe { script_error(rt.cx()) })?;
+                timer.context();
+
+                rooted!(in(rt.cx()) let script = unsafe {
+                    let options = CompileOptionsWrapper::new(rt.cx(), "inline.js", 1);
+                    Compile1(rt.cx(), options.ptr, &mut transform_str_to_source_text(&file))
+                });
+                if script.is_null() {
+                    return Err(unsafe { script_error(rt.cx()) }.into());
+                }
+                timer.compile();
+
+                repeat(runs, timer, || unsafe {
+                    if jsapi::JS_ExecuteScript(rt.cx(), script.handle(), rval.handle_mut()) {
+                        Ok(())
+                    } else {
+                        Err(script_error(rt.cx()))
+                    }
+                })
             },
         )
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,10 @@
 use std::path::Path;
 
-use v8::{Context, ContextScope, HandleScope, Isolate, Local, Object};
+use v8::{Context, ContextScope, HandleScope, Isolate, Local, Object, TryCatch};
 
-use crate::errors::TestError;
-use crate::js::runner::run;
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::js::runner::{repeat, run, PhaseTimer};
 use crate::js::{JSEngine, JSRunner};
 use crate::validator::Validator;
 use crate::{Engine, TestResult};
@@ -18,6 +19,18 @@ impl V8 {
             return Err(TestError::AlreadyInitialized);
         }
 
+        Self::initialize();
+
+        Ok(V8)
+    }
+
+    /// Initializes the platform, unless it already is. Engine children return from
+    /// `procspawn::init` before `main` creates a `V8` runner, so they have to do it themselves.
+    pub(crate) fn initialize() {
+        if unsafe { INITIALIZED } {
+            return;
+        }
+
         let platform = v8::new_default_platform(0, false).make_shared();
         v8::V8::initialize_platform(platform);
         v8::V8::initialize();
@@ -25,8 +38,6 @@ impl V8 {
         unsafe {
             INITIALIZED = true;
         }
-
-        Ok(V8)
     }
 
     fn register_console(s: &mut ContextScope<HandleScope>, global: Local<Object>) {
@@ -49,7 +60,7 @@ impl V8 {
                     out.push(' ');
                 }
                 out.pop();
-                println!("{}", out);
+                console::log(&out);
             },
         )
         .get_function(s)
@@ -68,6 +79,25 @@ impl V8 {
 
         global.set(s, console_key.into(), console_val.into());
     }
+
+    pub(crate) fn script_error(tc: &mut TryCatch<HandleScope>) -> ScriptError {
+        let mut error = ScriptError::default();
+
+        if let Some(exception) = tc.exception() {
+            error.message = exception.to_rust_string_lossy(tc);
+        }
+
+        if let Some(message) = tc.message() {
+            error.line = message.get_line_number(tc).map(|line| line as u32);
+            error.column = Some(message.get_start_column() as u32 + 1);
+        }
+
+        if let Some(stack) = tc.stack_trace() {
+            error.stack = Some(stack.to_rust_string_lossy(tc));
+        }
+
+        error
+    }
 }
 
 impl Drop for V8 {
@@ -86,27 +116,35 @@ impl JSRunner for V8 {
         path: &Path,
         validator: &'a Validator,
     ) -> Result<TestResult, TestError> {
-        run(
-            path,
-            validator,
-            Engine::JS(JSEngine::V8),
-            |(file, reruns)| {
-                let isolate = &mut Isolate::new(Default::default());
-                let hs = &mut HandleScope::new(isolate);
-                let c = Context::new(hs);
-                let s = &mut ContextScope::new(hs, c);
+        run(path, validator, Engine::JS(JSEngine::V8), |(file, runs)| {
+            Self::initialize();
 
-                let global = c.global(s);
+            let mut timer = PhaseTimer::start();
 
-                Self::register_console(s, global);
+            let isolate = &mut Isolate::new(Default::default());
+            timer.init();
 
-                let code = v8::String::new(s, &file).unwrap();
-                let script = v8::Script::compile(s, code, None).unwrap();
+            let hs = &mut HandleScope::new(isolate);
+            let c = Context::new(hs);
+            let s = &mut ContextScope::new(hs, c);
 
-                for _ in 0..reruns {
-                    script.run(s).unwrap();
-                }
-            },
-        )
+            let global = c.global(s);
+
+            Self::register_console(s, global);
+            timer.context();
+
+            let tc = &mut TryCatch::new(s);
+
+            let code = v8::String::new(tc, &file).unwrap();
+            let Some(script) = v8::Script::compile(tc, code, None) else {
+                return Err(Self::script_error(tc).into());
+            };
+            timer.compile();
+
+            repeat(runs, timer, || match script.run(tc) {
+                Some(_) => Ok(()),
+                None => Err(Self::script_error(tc)),
+            })
+        })
     }
 }
@@ -3,24 +3,161 @@
 pub use test::*;
 
 use crate::errors::TestError;
+use crate::js::boa::Boa;
 use crate::js::deno::Deno;
 use crate::js::duktape::Duktape;
+use crate::js::external::{External, ExternalEngine};
 use crate::js::javascriptcore::JavaScriptCore;
+use crate::js::quickjs::QuickJS;
 use crate::js::JSRunner;
+use crate::wasm::wasmer::Wasmer;
+use crate::wasm::wasmi::Wasmi;
+use crate::wasm::wasmtime::Wasmtime;
+use crate::wasm::{WasmRunner, WasmerCompiler};
 
 mod benchmark;
+mod console;
 mod errors;
+pub mod export;
 pub mod js;
+pub mod limits;
+mod manifest;
+pub mod pairs;
 mod resources;
+mod runner;
+pub mod stats;
 mod test;
+#[cfg(test)]
+mod test_util;
 pub mod validator;
 pub mod wasm;
+pub mod worker;
 
 pub struct Test;
 
+/// An engine the harness knows about
+#[derive(Debug, Clone, Copy)]
+pub struct EngineInfo {
+    pub name: &'static str,
+    /// Other names the engine can be selected with
+    pub aliases: &'static [&'static str],
+    /// Cargo feature the engine is behind, `None` if it is always compiled in
+    pub feature: Option<&'static str>,
+    pub enabled: bool,
+}
+
+pub const JS_ENGINES: &[EngineInfo] = &[
+    EngineInfo {
+        name: "v8",
+        aliases: &[],
+        feature: Some("v8"),
+        enabled: cfg!(feature = "v8"),
+    },
+    EngineInfo {
+        name: "spidermonkey",
+        aliases: &["sm", "mozjs"],
+        feature: Some("mozjs"),
+        enabled: cfg!(feature = "mozjs"),
+    },
+    EngineInfo {
+        name: "javascriptcore",
+        aliases: &["jsc"],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "deno",
+        aliases: &[],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "duktape",
+        aliases: &["dt"],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "boa",
+        aliases: &[],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "quickjs",
+        aliases: &["qjs"],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "chakra",
+        aliases: &["chakracore"],
+        feature: Some("chakra"),
+        enabled: cfg!(feature = "chakra"),
+    },
+];
+
+/// The wasm engines, the JS engines that also run wasm are listed with a `-wasm` suffix
+pub const WASM_ENGINES: &[EngineInfo] = &[
+    EngineInfo {
+        name: "wasmtime",
+        aliases: &[],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "wasmer",
+        aliases: &["wasmer-cranelift"],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "wasmer-singlepass",
+        aliases: &[],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "wasmer-llvm",
+        aliases: &[],
+        feature: Some("wasmer-llvm"),
+        enabled: cfg!(feature = "wasmer-llvm"),
+    },
+    EngineInfo {
+        name: "wasmi",
+        aliases: &[],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "v8-wasm",
+        aliases: &[],
+        feature: Some("v8"),
+        enabled: cfg!(feature = "v8"),
+    },
+    EngineInfo {
+        name: "spidermonkey-wasm",
+        aliases: &["sm-wasm", "mozjs-wasm"],
+        feature: Some("mozjs"),
+        enabled: cfg!(feature = "mozjs"),
+    },
+    EngineInfo {
+        name: "javascriptcore-wasm",
+        aliases: &["jsc-wasm"],
+        feature: None,
+        enabled: true,
+    },
+    EngineInfo {
+        name: "deno-wasm",
+        aliases: &[],
+        feature: None,
+        enabled: true,
+    },
+];
+
 #[cfg(all(feature = "mozjs", feature = "v8"))]
 compile_error!(
-    "Features `mozjs` and `v8` are mutually exclusive and cannot be enabled at the same time."
+    "Features `mozjs` and `v8` are mutually exclusive and cannot be enabled at the same time, build a worker for one of them instead."
 );
 
 impl Test {
@@ -59,6 +196,189 @@ impl Test {
     pub fn duktape(&self) -> Result<Box<dyn JSRunner>, TestError> {
         Ok(Box::new(Duktape::new()))
     }
+
+    pub fn boa(&self) -> Result<Box<dyn JSRunner>, TestError> {
+        Ok(Box::new(Boa::new()))
+    }
+
+    pub fn quickjs(&self) -> Result<Box<dyn JSRunner>, TestError> {
+        Ok(Box::new(QuickJS::new()))
+    }
+
+    pub fn chakra(&self) -> Result<Box<dyn JSRunner>, TestError> {
+        #[cfg(feature = "chakra")]
+        {
+            use crate::js::chakra::Chakra;
+            Ok(Box::new(Chakra::new()))
+        }
+        #[cfg(not(feature = "chakra"))]
+        Err(TestError::FeatureNotEnabled("chakra"))
+    }
+
+    /// Runs the engine through its command line
+    pub fn external(&self, engine: &ExternalEngine) -> Result<Box<dyn JSRunner>, TestError> {
+        Ok(Box::new(External::new(engine.clone())))
+    }
+
+    /// Constructs the JS runner called `name` or one of its aliases
+    pub fn js_runner(&self, name: &str) -> Result<Box<dyn JSRunner>, TestError> {
+        match name.to_lowercase().as_str() {
+            "v8" => self.v8(),
+            "spidermonkey" | "sm" | "mozjs" => self.spidermonkey(),
+            "javascriptcore" | "jsc" => self.javascriptcore(),
+            "deno" => self.deno(),
+            "duktape" | "dt" => self.duktape(),
+            "boa" => self.boa(),
+            "quickjs" | "qjs" => self.quickjs(),
+            "chakra" | "chakracore" => self.chakra(),
+            _ => Err(TestError::String(format!("Unknown engine {}", name))),
+        }
+    }
+
+    /// Constructs every JS runner enabled in this build, skipping the ones behind a disabled feature
+    pub fn js_runners(&self) -> Result<Vec<Box<dyn JSRunner>>, TestError> {
+        let constructors = [
+            Self::v8,
+            Self::spidermonkey,
+            Self::javascriptcore,
+            Self::deno,
+            Self::duktape,
+            Self::boa,
+            Self::quickjs,
+            Self::chakra,
+        ];
+
+        let mut runners = Vec::with_capacity(constructors.len());
+
+        for constructor in constructors {
+            match constructor(self) {
+                Ok(runner) => runners.push(runner),
+                Err(TestError::FeatureNotEnabled(_)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(runners)
+    }
+
+    pub fn wasmtime(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(Wasmtime::new()))
+    }
+
+    /// Wasmer with Cranelift, its default compiler
+    pub fn wasmer(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(Wasmer::new(WasmerCompiler::Cranelift)?))
+    }
+
+    pub fn wasmer_singlepass(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(Wasmer::new(WasmerCompiler::Singlepass)?))
+    }
+
+    pub fn wasmer_llvm(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(Wasmer::new(WasmerCompiler::LLVM)?))
+    }
+
+    pub fn wasmi(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(Wasmi::new()))
+    }
+
+    pub fn v8_wasm(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        #[cfg(feature = "v8")]
+        {
+            use crate::wasm::v8::V8;
+            Ok(Box::new(V8::new()))
+        }
+        #[cfg(not(feature = "v8"))]
+        Err(TestError::FeatureNotEnabled("v8"))
+    }
+
+    pub fn spidermonkey_wasm(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        #[cfg(feature = "mozjs")]
+        {
+            use crate::wasm::spidermonkey::SpiderMonkey;
+            Ok(Box::new(SpiderMonkey::new()))
+        }
+        #[cfg(not(feature = "mozjs"))]
+        Err(TestError::FeatureNotEnabled("mozjs"))
+    }
+
+    pub fn javascriptcore_wasm(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(wasm::javascriptcore::JavaScriptCore::new()))
+    }
+
+    pub fn deno_wasm(&self) -> Result<Box<dyn WasmRunner>, TestError> {
+        Ok(Box::new(wasm::deno::Deno::new()))
+    }
+
+    /// Constructs the wasm runner called `name` or one of its aliases
+    pub fn wasm_runner(&self, name: &str) -> Result<Box<dyn WasmRunner>, TestError> {
+        match name.to_lowercase().as_str() {
+            "wasmtime" => self.wasmtime(),
+            "wasmer" | "wasmer-cranelift" => self.wasmer(),
+            "wasmer-singlepass" => self.wasmer_singlepass(),
+            "wasmer-llvm" => self.wasmer_llvm(),
+            "wasmi" => self.wasmi(),
+            "v8-wasm" => self.v8_wasm(),
+            "spidermonkey-wasm" | "sm-wasm" | "mozjs-wasm" => self.spidermonkey_wasm(),
+            "javascriptcore-wasm" | "jsc-wasm" => self.javascriptcore_wasm(),
+            "deno-wasm" => self.deno_wasm(),
+            _ => Err(TestError::String(format!("Unknown engine {}", name))),
+        }
+    }
+
+    /// Constructs every wasm runner enabled in this build, skipping the ones behind a disabled
+    /// feature
+    pub fn wasm_runners(&self) -> Result<Vec<Box<dyn WasmRunner>>, TestError> {
+        let constructors = [
+            Self::wasmtime,
+            Self::wasmer,
+            Self::wasmer_singlepass,
+            Self::wasmer_llvm,
+            Self::wasmi,
+            Self::v8_wasm,
+            Self::spidermonkey_wasm,
+            Self::javascriptcore_wasm,
+            Self::deno_wasm,
+        ];
+
+        let mut runners = Vec::with_capacity(constructors.len());
+
+        for constructor in constructors {
+            match constructor(self) {
+                Ok(runner) => runners.push(runner),
+                Err(TestError::FeatureNotEnabled(_)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(runners)
+    }
+
+    /// Constructs the runners called `names`, each one a JS or a wasm engine
+    pub fn runners(&self, names: &[impl AsRef<str>]) -> Result<Runners, TestError> {
+        let mut runners = Runners::default();
+
+        for name in names {
+            let name = name.as_ref();
+
+            if WASM_ENGINES.iter().any(|engine| engine.is(name)) {
+                runners.wasm.push(self.wasm_runner(name)?);
+            } else {
+                runners.js.push(self.js_runner(name)?);
+            }
+        }
+
+        Ok(runners)
+    }
+}
+
+impl EngineInfo {
+    /// Whether `name` is the name or one of the aliases of this engine, ignoring case
+    pub fn is(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        self.name == name || self.aliases.contains(&name.as_str())
+    }
 }
 
 impl Default for Test {
@@ -0,0 +1,65 @@
+use nix::sys::resource::{getrlimit, setrlimit, Resource};
+use std::io;
+
+/// Runs in the child between `fork` and `exec`
+pub(crate) type Hook = Box<dyn FnMut() -> io::Result<()> + Send + Sync>;
+
+/// # Limits
+/// `setrlimit` limits applied to the engine child before the engine starts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// Maximum size of the virtual address space in bytes (`RLIMIT_AS`).
+    /// Engines reserve a lot more address space than they actually use, V8 will not even start with a few hundred MiB.
+    pub address_space: Option<u64>,
+    /// CPU time in seconds (`RLIMIT_CPU`), the engine gets `SIGXCPU` when it is used up and `SIGKILL` a second later
+    pub cpu_time: Option<u64>,
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`)
+    pub open_files: Option<u64>,
+    /// Whether a crashing engine may write a core dump (`RLIMIT_CORE`)
+    pub core_dumps: bool,
+}
+
+impl Limits {
+    /// Applies the limits to the current process, they are inherited by everything it executes
+    pub(crate) fn apply(&self) -> nix::Result<()> {
+        if let Some(bytes) = self.address_space {
+            limit(Resource::RLIMIT_AS, bytes, bytes)?;
+        }
+
+        if let Some(seconds) = self.cpu_time {
+            limit(Resource::RLIMIT_CPU, seconds, seconds + 1)?;
+        }
+
+        if let Some(files) = self.open_files {
+            limit(Resource::RLIMIT_NOFILE, files, files)?;
+        }
+
+        if !self.core_dumps {
+            limit(Resource::RLIMIT_CORE, 0, 0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Makes `spawner` apply the limits to every process it spawns, `pre_exec` being its `pre_exec` method
+    pub(crate) fn pre_exec_hook<T>(
+        self,
+        spawner: &mut T,
+        pre_exec: unsafe fn(&mut T, Hook) -> &mut T,
+    ) {
+        let hook: Hook = Box::new(move || self.apply().map_err(io::Error::from));
+
+        // SAFETY: `setrlimit` is async-signal-safe and `apply` doesn't allocate
+        unsafe {
+            pre_exec(spawner, hook);
+        }
+    }
+}
+
+/// Lowers a limit, we can't go above the current hard limit without privileges
+fn limit(resource: Resource, soft: u64, hard: u64) -> nix::Result<()> {
+    let (_, current) = getrlimit(resource)?;
+    let hard = hard.min(current);
+
+    setrlimit(resource, soft.min(hard), hard)
+}
@@ -0,0 +1,456 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::errors::TestError;
+use crate::limits::Limits;
+use crate::validator::{parse_header, File, HTTPMethod, Validator, HTTP};
+
+/// # Manifest
+/// Sidecar file describing how a script is validated, e.g. `foo.js` + `foo.test.toml`
+///
+/// ```toml
+/// reruns = 100
+/// warmup = 10
+/// timeout = 30
+/// entry = "run" # wasm only
+/// fuel = 1000000000 # wasm only
+/// wasi = true # wasm only
+/// args = ["--size", "100"] # wasm only, needs `wasi`
+/// console = ["Hello World !"]
+///
+/// [[files]]
+/// path = "out.txt"
+/// sha256 = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+///
+/// [[http]]
+/// url = "http://localhost:8080/"
+/// method = "POST"
+/// payload = "ping"
+/// headers = ["Content-Type: text/plain"]
+/// response = "pong"
+/// response_code = 200
+/// benchmark = { duration = 10, validate = true }
+///
+/// [limits]
+/// address_space = 4096 # MiB
+/// cpu_time = 10 # seconds
+/// open_files = 64
+/// core_dumps = false
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default = "default_reruns")]
+    reruns: u32,
+    #[serde(default)]
+    warmup: u32,
+    /// Timeout in seconds
+    timeout: Option<f64>,
+    entry: Option<String>,
+    fuel: Option<u64>,
+    #[serde(default)]
+    wasi: bool,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    console: Vec<String>,
+    #[serde(default)]
+    files: Vec<ManifestFile>,
+    #[serde(default)]
+    http: Vec<ManifestHTTP>,
+    #[serde(default)]
+    limits: ManifestLimits,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    path: String,
+    sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestHTTP {
+    url: String,
+    #[serde(default = "default_method")]
+    method: String,
+    #[serde(default)]
+    payload: String,
+    #[serde(default)]
+    headers: Vec<String>,
+    #[serde(default)]
+    response: String,
+    #[serde(default = "default_response_code")]
+    response_code: u16,
+    benchmark: Option<ManifestBenchmark>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestBenchmark {
+    /// Duration in seconds
+    duration: f64,
+    #[serde(default)]
+    validate: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestLimits {
+    /// Address space in MiB
+    address_space: Option<u64>,
+    /// CPU time in seconds
+    cpu_time: Option<u64>,
+    open_files: Option<u64>,
+    #[serde(default)]
+    core_dumps: bool,
+}
+
+fn default_reruns() -> u32 {
+    1
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_response_code() -> u16 {
+    200
+}
+
+impl Validator {
+    /// Path of the manifest belonging to `script`, `foo.js` => `foo.test.toml`
+    pub fn manifest_path(script: &Path) -> PathBuf {
+        script.with_extension("test.toml")
+    }
+
+    /// Loads the sidecar manifest of `script`, `Ok(None)` if the script has none
+    pub fn for_script(script: &Path) -> Result<Option<Validator>, TestError> {
+        let manifest = Self::manifest_path(script);
+
+        if !manifest.is_file() {
+            return Ok(None);
+        }
+
+        Self::from_manifest(&manifest).map(Some)
+    }
+
+    pub fn from_manifest(path: &Path) -> Result<Validator, TestError> {
+        let content = fs::read_to_string(path)?;
+
+        Self::parse_manifest(&content)
+            .map_err(|err| TestError::Manifest(format!("{}: {}", path.display(), err)))
+    }
+
+    fn parse_manifest(content: &str) -> Result<Validator, String> {
+        let manifest: Manifest = toml::from_str(content).map_err(|err| err.to_string())?;
+
+        if manifest.reruns == 0 {
+            return Err("`reruns` must be at least 1".to_string());
+        }
+
+        let timeout = match manifest.timeout {
+            Some(timeout) if !timeout.is_finite() || timeout <= 0.0 => {
+                return Err("`timeout` must be a positive number of seconds".to_string())
+            }
+            Some(timeout) => Some(Duration::from_secs_f64(timeout)),
+            None => None,
+        };
+
+        let files = manifest
+            .files
+            .into_iter()
+            .enumerate()
+            .map(|(idx, file)| {
+                file.into_file()
+                    .map_err(|err| format!("files[{}]: {}", idx, err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let http = manifest
+            .http
+            .into_iter()
+            .enumerate()
+            .map(|(idx, http)| {
+                http.into_http()
+                    .map_err(|err| format!("http[{}]: {}", idx, err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if manifest.entry.as_deref() == Some("") {
+            return Err("`entry` must not be empty".to_string());
+        }
+
+        if !manifest.args.is_empty() && !manifest.wasi {
+            return Err("`args` need `wasi = true`".to_string());
+        }
+
+        let limits = manifest
+            .limits
+            .into_limits()
+            .map_err(|err| format!("limits: {}", err))?;
+
+        Ok(Validator {
+            files,
+            console: manifest.console,
+            http,
+            reruns: manifest.reruns,
+            warmup: manifest.warmup,
+            timeout,
+            limits,
+            entry: manifest.entry,
+            fuel: manifest.fuel,
+            wasi: manifest.wasi,
+            args: manifest.args,
+        })
+    }
+}
+
+impl ManifestFile {
+    fn into_file(self) -> Result<File, String> {
+        if self.path.is_empty() {
+            return Err("`path` must not be empty".to_string());
+        }
+
+        if self.sha256.len() != 64 || !self.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "`sha256` of {} is not a hex encoded SHA-256 digest",
+                self.path
+            ));
+        }
+
+        Ok(File {
+            path: self.path,
+            sha256: self.sha256.to_lowercase(),
+        })
+    }
+}
+
+impl ManifestHTTP {
+    fn into_http(self) -> Result<HTTP, String> {
+        let method = match self.method.to_uppercase().as_str() {
+            "GET" => HTTPMethod::GET,
+            "POST" => HTTPMethod::POST,
+            "PUT" => HTTPMethod::PUT,
+            "DELETE" => HTTPMethod::DELETE,
+            "PATCH" => HTTPMethod::PATCH,
+            _ => return Err(format!("unknown method `{}`", self.method)),
+        };
+
+        for header in &self.headers {
+            parse_header(header)?;
+        }
+
+        if !(100..=599).contains(&self.response_code) {
+            return Err(format!(
+                "`response_code` {} is not a valid HTTP status code",
+                self.response_code
+            ));
+        }
+
+        let (benchmark, benchmark_duration, benchmark_validate) = match self.benchmark {
+            Some(bench) => {
+                if !bench.duration.is_finite() || bench.duration <= 0.0 {
+                    return Err(
+                        "`benchmark.duration` must be a positive number of seconds".to_string()
+                    );
+                }
+
+                (
+                    true,
+                    Duration::from_secs_f64(bench.duration),
+                    bench.validate,
+                )
+            }
+            None => (false, Duration::ZERO, false),
+        };
+
+        Ok(HTTP {
+            payload: self.payload,
+            url: self.url,
+            method,
+            headers: self.headers,
+            response: self.response,
+            response_code: self.response_code,
+            benchmark,
+            benchmark_duration,
+            benchmark_validate,
+        })
+    }
+}
+
+impl ManifestLimits {
+    fn into_limits(self) -> Result<Limits, String> {
+        for (name, value) in [
+            ("address_space", self.address_space),
+            ("cpu_time", self.cpu_time),
+            ("open_files", self.open_files),
+        ] {
+            if value == Some(0) {
+                return Err(format!("`{}` must be at least 1", name));
+            }
+        }
+
+        let address_space = match self.address_space {
+            Some(mib) => Some(
+                mib.checked_mul(1024 * 1024)
+                    .ok_or("`address_space` is too large")?,
+            ),
+            None => None,
+        };
+
+        Ok(Limits {
+            address_space,
+            cpu_time: self.cpu_time,
+            open_files: self.open_files,
+            core_dumps: self.core_dumps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    fn error(manifest: &str) -> String {
+        Validator::parse_manifest(manifest)
+            .err()
+            .expect("the manifest should be rejected")
+    }
+
+    #[test]
+    fn parses_a_full_manifest() {
+        let manifest = format!(
+            r#"
+            reruns = 100
+            warmup = 10
+            timeout = 1.5
+            console = ["Hello World !"]
+
+            [[files]]
+            path = "out.txt"
+            sha256 = "{}"
+
+            [[http]]
+            url = "http://localhost:8080/"
+            method = "post"
+            headers = ["Content-Type: text/plain"]
+            benchmark = {{ duration = 10 }}
+
+            [limits]
+            address_space = 64
+            cpu_time = 10
+            "#,
+            SHA256.to_uppercase()
+        );
+
+        let validator = Validator::parse_manifest(&manifest).ok().unwrap();
+
+        assert_eq!(validator.reruns, 100);
+        assert_eq!(validator.warmup, 10);
+        assert_eq!(validator.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(validator.console, ["Hello World !"]);
+        assert_eq!(validator.files[0].sha256, SHA256);
+        assert!(matches!(validator.http[0].method, HTTPMethod::POST));
+        assert_eq!(
+            validator.http[0].benchmark_duration,
+            Duration::from_secs(10)
+        );
+        assert_eq!(validator.limits.address_space, Some(64 * 1024 * 1024));
+        assert_eq!(validator.limits.cpu_time, Some(10));
+    }
+
+    #[test]
+    fn defaults_to_one_run() {
+        let validator = Validator::parse_manifest("").ok().unwrap();
+
+        assert_eq!(validator.reruns, 1);
+        assert_eq!(validator.warmup, 0);
+        assert_eq!(validator.timeout, None);
+        assert!(validator.files.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(error("rerun = 10").contains("unknown field `rerun`"));
+        assert!(error("[limits]\nmemory = 10").contains("unknown field `memory`"));
+        assert!(error("[[files]]\npath = \"a\"\nsha = \"b\"").contains("unknown field `sha`"));
+    }
+
+    #[test]
+    fn rejects_a_bad_sha256() {
+        let err = error("[[files]]\npath = \"out.txt\"\nsha256 = \"abc\"");
+        assert!(err.starts_with("files[0]:"), "{}", err);
+
+        let not_hex = SHA256.replace('b', "g");
+        let err = error(&format!(
+            "[[files]]\npath = \"out.txt\"\nsha256 = \"{}\"",
+            not_hex
+        ));
+        assert!(err.contains("not a hex encoded SHA-256 digest"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_a_bad_method() {
+        let err = error("[[http]]\nurl = \"http://localhost/\"\nmethod = \"FETCH\"");
+        assert_eq!(err, "http[0]: unknown method `FETCH`");
+    }
+
+    #[test]
+    fn checks_headers() {
+        let http = |header: &str| {
+            format!(
+                "[[http]]\nurl = \"http://localhost/\"\nheaders = [\"{}\"]",
+                header
+            )
+        };
+
+        assert_eq!(
+            error(&http("Content-Type")),
+            "http[0]: header `Content-Type` is not in the form `Key: Value`"
+        );
+        assert_eq!(
+            error(&http("Content Type: text/plain")),
+            "http[0]: header `Content Type: text/plain` has an invalid name"
+        );
+        assert_eq!(
+            error(&http("X-Id: a\\u0001b")),
+            "http[0]: header `X-Id: a\u{1}b` has an invalid value"
+        );
+
+        let validator =
+            Validator::parse_manifest(&http("Referer: http://localhost:8080/")).unwrap();
+        let (key, value) = parse_header(&validator.http[0].headers[0]).unwrap();
+        assert_eq!(key, "referer");
+        assert_eq!(value, "http://localhost:8080/");
+    }
+
+    #[test]
+    fn rejects_zero_limits() {
+        assert_eq!(
+            error("[limits]\naddress_space = 0"),
+            "limits: `address_space` must be at least 1"
+        );
+        assert_eq!(
+            error("[limits]\ncpu_time = 0"),
+            "limits: `cpu_time` must be at least 1"
+        );
+        assert_eq!(
+            error("[limits]\nopen_files = 0"),
+            "limits: `open_files` must be at least 1"
+        );
+    }
+
+    #[test]
+    fn rejects_zero_reruns_and_timeouts() {
+        assert_eq!(error("reruns = 0"), "`reruns` must be at least 1");
+        assert!(error("timeout = 0").contains("positive number of seconds"));
+        assert!(error("timeout = -1").contains("positive number of seconds"));
+    }
+}
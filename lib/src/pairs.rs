@@ -0,0 +1,310 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::TestError;
+use crate::js::JSEngine;
+use crate::wasm::WasmEngine;
+use crate::{Engine, TestResult};
+
+/// # JS vs wasm
+/// A `foo.js` next to a `foo.wasm` (or `foo.wat`) implements the same kernel in both. The script
+/// runs on the JS engines and the module on the wasm engines, the results are then paired up by
+/// file name to answer whether the workload is faster as wasm.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pair {
+    pub js: PathBuf,
+    pub wasm: PathBuf,
+}
+
+impl Pair {
+    /// The pair `path` belongs to, `path` being either the script or the module
+    pub fn of(path: &Path) -> Option<Pair> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("js") => {
+                let wasm = ["wasm", "wat"]
+                    .iter()
+                    .map(|ext| path.with_extension(ext))
+                    .find(|wasm| wasm.is_file())?;
+
+                Some(Pair {
+                    js: path.to_path_buf(),
+                    wasm,
+                })
+            }
+            Some("wasm" | "wat") => {
+                let js = path.with_extension("js");
+                if !js.is_file() {
+                    return None;
+                }
+
+                let pair = Pair::of(&js)?;
+
+                // a `foo.wat` next to a `foo.wasm` isn't part of the pair
+                (pair.wasm == path).then_some(pair)
+            }
+            _ => None,
+        }
+    }
+
+    /// Both files, to be run like any other tests
+    pub fn paths(&self) -> [PathBuf; 2] {
+        [self.js.clone(), self.wasm.clone()]
+    }
+}
+
+/// Every pair under `path`, or the pair of the file at `path`
+pub fn find_pairs(path: &Path) -> Result<Vec<Pair>, TestError> {
+    if !path.is_dir() {
+        return Pair::of(path).map(|pair| vec![pair]).ok_or_else(|| {
+            TestError::String(format!(
+                "{} has no JS script or wasm module next to it",
+                path.display()
+            ))
+        });
+    }
+
+    let mut pairs = vec![];
+
+    for entry in path.read_dir()? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            pairs.append(&mut find_pairs(&path)?);
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("js") {
+            pairs.extend(Pair::of(&path));
+        }
+    }
+
+    pairs.sort_by(|a, b| a.js.cmp(&b.js));
+
+    Ok(pairs)
+}
+
+/// The results of a pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairResult {
+    pub js: Box<Path>,
+    pub wasm: Box<Path>,
+    /// Whether every run printed what the JS runs printed. Runs of the module that didn't print
+    /// anything are left out, without WASI a module has no way to print. `None` if none printed.
+    pub console_valid: Option<bool>,
+    pub ratios: Vec<Ratio>,
+}
+
+/// The speed of the script on a JS engine against the module on a wasm engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ratio {
+    pub js_engine: Engine,
+    pub wasm_engine: Engine,
+    /// Median of the script's samples in nanoseconds, `None` if the run didn't succeed
+    pub js_median: Option<f64>,
+    /// Median of the module's samples in nanoseconds, `None` if the run didn't succeed
+    pub wasm_median: Option<f64>,
+    /// `js_median / wasm_median`, above 1 the module is faster
+    pub ratio: Option<f64>,
+}
+
+/// The JS engine whose `WebAssembly` API runs modules as `engine`, `None` for the standalone
+/// runtimes
+fn js_engine_of(engine: &WasmEngine) -> Option<JSEngine> {
+    match engine {
+        WasmEngine::V8 => Some(JSEngine::V8),
+        WasmEngine::SpiderMonkey => Some(JSEngine::SpiderMonkey),
+        WasmEngine::JavaScriptCore => Some(JSEngine::JavaScriptCore),
+        WasmEngine::Deno => Some(JSEngine::Deno),
+        _ => None,
+    }
+}
+
+/// Pairs up the results of the scripts and modules in `results`. A JS engine is compared against
+/// its own `WebAssembly` API and against every standalone runtime.
+pub fn pair_results(results: &[TestResult]) -> Vec<PairResult> {
+    let mut pairs: Vec<(Pair, Vec<&TestResult>, Vec<&TestResult>)> = vec![];
+
+    for res in results {
+        let Some(pair) = Pair::of(&res.path) else {
+            continue;
+        };
+
+        let index = match pairs.iter().position(|(p, _, _)| *p == pair) {
+            Some(index) => index,
+            None => {
+                pairs.push((pair, vec![], vec![]));
+                pairs.len() - 1
+            }
+        };
+
+        match res.engine {
+            Engine::JS(_) => pairs[index].1.push(res),
+            Engine::Wasm(_) => pairs[index].2.push(res),
+        }
+    }
+
+    pairs
+        .into_iter()
+        .map(|(pair, js, wasm)| {
+            let expected = js.iter().find(|res| res.success).map(|res| &res.console);
+
+            let console_valid = match expected {
+                Some(expected) if wasm.iter().any(|res| !res.console.is_empty()) => Some(
+                    js.iter()
+                        .filter(|res| res.success)
+                        .chain(wasm.iter().filter(|res| !res.console.is_empty()))
+                        .all(|res| res.console == *expected),
+                ),
+                _ => None,
+            };
+
+            let mut ratios = vec![];
+            for js_res in &js {
+                for wasm_res in &wasm {
+                    let (Engine::JS(js_engine), Engine::Wasm(wasm_engine)) =
+                        (&js_res.engine, &wasm_res.engine)
+                    else {
+                        continue;
+                    };
+
+                    if js_engine_of(wasm_engine).is_some_and(|engine| engine != *js_engine) {
+                        continue;
+                    }
+
+                    let js_median = median(js_res);
+                    let wasm_median = median(wasm_res);
+
+                    ratios.push(Ratio {
+                        js_engine: js_res.engine.clone(),
+                        wasm_engine: wasm_res.engine.clone(),
+                        js_median,
+                        wasm_median,
+                        ratio: match (js_median, wasm_median) {
+                            (Some(js), Some(wasm)) if wasm > 0.0 => Some(js / wasm),
+                            _ => None,
+                        },
+                    });
+                }
+            }
+
+            PairResult {
+                js: pair.js.into_boxed_path(),
+                wasm: pair.wasm.into_boxed_path(),
+                console_valid,
+                ratios,
+            }
+        })
+        .collect()
+}
+
+fn median(res: &TestResult) -> Option<f64> {
+    if !res.success {
+        return None;
+    }
+
+    res.stats.as_ref().map(|stats| stats.median)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::stats::Stats;
+
+    /// A directory with the files `names`, all empty
+    fn dir(names: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for name in names {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        dir
+    }
+
+    fn result(path: PathBuf, engine: Engine, samples: &[u64], success: bool) -> TestResult {
+        let mut res = TestResult::new(&path, engine);
+        res.samples = samples.to_vec();
+        res.stats = Stats::from_samples(samples);
+        res.success = success;
+        res
+    }
+
+    #[test]
+    fn pairs_files_with_the_same_stem() {
+        let dir = dir(&["a.js", "a.wat", "b.js", "c.wasm", "d.js", "d.wasm", "d.wat"]);
+        let path = |name: &str| dir.path().join(name);
+
+        let pairs = find_pairs(dir.path()).unwrap();
+        assert_eq!(
+            pairs,
+            [
+                Pair {
+                    js: path("a.js"),
+                    wasm: path("a.wat"),
+                },
+                Pair {
+                    js: path("d.js"),
+                    wasm: path("d.wasm"),
+                },
+            ]
+        );
+
+        assert_eq!(Pair::of(&path("d.wasm")), Some(pairs[1].clone()));
+        assert_eq!(Pair::of(&path("d.wat")), None);
+        assert_eq!(Pair::of(&path("b.js")), None);
+        assert_eq!(Pair::of(&path("c.wasm")), None);
+        assert!(find_pairs(&path("b.js")).is_err());
+    }
+
+    #[test]
+    fn ratios_need_both_sides_to_succeed() {
+        let dir = dir(&["a.js", "a.wasm"]);
+        let js = || dir.path().join("a.js");
+        let wasm = || dir.path().join("a.wasm");
+
+        let quickjs = Engine::JS(JSEngine::QuickJS);
+        let boa = Engine::JS(JSEngine::Boa);
+        let wasmtime = Engine::Wasm(WasmEngine::Wasmtime);
+        let wasmi = Engine::Wasm(WasmEngine::Wasmi);
+
+        let results = [
+            result(js(), quickjs.clone(), &[300, 100], true),
+            result(js(), boa.clone(), &[100], false),
+            result(wasm(), wasmtime.clone(), &[100], true),
+            result(wasm(), wasmi.clone(), &[], true),
+            // only compared against V8
+            result(wasm(), Engine::Wasm(WasmEngine::V8), &[100], true),
+        ];
+
+        let pairs = pair_results(&results);
+        assert_eq!(pairs.len(), 1);
+
+        let engines = pairs[0]
+            .ratios
+            .iter()
+            .map(|r| (&r.js_engine, &r.wasm_engine))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            engines,
+            [
+                (&quickjs, &wasmtime),
+                (&quickjs, &wasmi),
+                (&boa, &wasmtime),
+                (&boa, &wasmi),
+            ]
+        );
+
+        let ratios = pairs[0]
+            .ratios
+            .iter()
+            .map(|r| (r.js_median, r.wasm_median, r.ratio))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ratios,
+            [
+                (Some(200.0), Some(100.0), Some(2.0)),
+                (Some(200.0), None, None),
+                (None, Some(100.0), None),
+                (None, None, None),
+            ]
+        );
+    }
+}
@@ -2,9 +2,10 @@ use std::sync::atomic::AtomicBool;
 use std::sync::RwLock;
 use std::thread;
 
+use serde::{Deserialize, Serialize};
 use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceUsage {
     pub cpu: f32,
     pub mem: u64,
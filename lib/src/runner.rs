@@ -0,0 +1,439 @@
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::limits::{Hook, Limits};
+use crate::resources::ResourceMonitor;
+use crate::stats::Stats;
+use crate::validator::{HTTPResultType, Validator, DEFAULT_TIMEOUT};
+use crate::{Engine, Outcome, TestResult};
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender, TryRecvError};
+use nix::sys::resource::{getrusage, UsageWho};
+use nix::sys::signal::Signal;
+use nix::sys::time::TimeValLike;
+use nix::sys::wait::{waitpid, WaitStatus};
+use nix::unistd::Pid;
+use procspawn::SpawnError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often the child runs the script
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Runs {
+    pub reruns: u32,
+    /// Iterations run before the measured ones, their samples are discarded
+    pub warmup: u32,
+    /// Durations of the measured iterations in nanoseconds, sent as soon as they are measured, so
+    /// they survive the child being killed after a timeout
+    pub samples: IpcSender<u64>,
+    /// Engines that can interrupt a script stop it on their own after this, before they get killed
+    pub timeout: Duration,
+}
+
+/// Time engines that stop scripts on their own get to report back before they are killed
+const INTERRUPT_GRACE: Duration = Duration::from_secs(1);
+
+/// Durations of the engine phases in microseconds
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Phases {
+    /// Creating the engine / runtime / isolate
+    pub init: u64,
+    /// Creating the context and registering globals like `console`
+    pub context: u64,
+    /// Parsing and compiling the script, 0 if the engine only compiles as part of running it
+    pub compile: u64,
+    /// Instantiating the wasm module, 0 for JS
+    pub instantiate: u64,
+    /// Running the script, including warmup
+    pub run: u64,
+}
+
+/// Measures the phases in the child, every call ends the phase that was running since the last one
+pub(crate) struct PhaseTimer {
+    last: Instant,
+    phases: Phases,
+}
+
+impl PhaseTimer {
+    pub fn start() -> Self {
+        PhaseTimer {
+            last: Instant::now(),
+            phases: Phases::default(),
+        }
+    }
+
+    fn lap(&mut self) -> u64 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_micros() as u64;
+        self.last = now;
+        elapsed
+    }
+
+    pub fn init(&mut self) {
+        self.phases.init = self.lap();
+    }
+
+    pub fn context(&mut self) {
+        self.phases.context = self.lap();
+    }
+
+    pub fn compile(&mut self) {
+        self.phases.compile = self.lap();
+    }
+
+    pub fn instantiate(&mut self) {
+        self.phases.instantiate = self.lap();
+    }
+}
+
+/// What the engine child sends back to the harness
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ChildOutput {
+    pub console: String,
+    pub phases: Phases,
+}
+
+/// Why the engine child stopped before running the script to the end
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum ChildError {
+    /// The script threw an exception or trapped
+    Script(ScriptError),
+    /// The engine interrupted the script after `Runs::timeout`
+    Timeout,
+    /// The engine itself failed, before it got to the script
+    Engine(String),
+}
+
+impl From<ScriptError> for ChildError {
+    fn from(err: ScriptError) -> Self {
+        ChildError::Script(err)
+    }
+}
+
+/// Result of the engine child, exceptions thrown by the script are reported instead of panicking
+pub(crate) type ChildResult = Result<ChildOutput, ChildError>;
+
+/// Runs the script `warmup + reruns` times in the child, timing every iteration individually.
+/// Every rerun prints the same again, so only the console output of the first run is kept.
+pub(crate) fn repeat<E: Into<ChildError>>(
+    runs: Runs,
+    mut timer: PhaseTimer,
+    mut run: impl FnMut() -> Result<(), E>,
+) -> ChildResult {
+    let mut output = ChildOutput::default();
+
+    timer.lap();
+
+    for i in 0..runs.warmup + runs.reruns {
+        let iteration = Instant::now();
+        run().map_err(Into::into)?;
+        let elapsed = iteration.elapsed().as_nanos() as u64;
+
+        if i >= runs.warmup {
+            // the harness is gone if this fails, nobody is left to care about the sample
+            runs.samples.send(elapsed).ok();
+        }
+
+        let console = console::take();
+        if i == 0 {
+            output.console = console;
+        }
+    }
+
+    timer.phases.run = timer.lap();
+    output.phases = timer.phases;
+
+    Ok(output)
+}
+
+/// User + system time of all children that were waited for, in microseconds
+pub(crate) fn children_cpu_time() -> u64 {
+    getrusage(UsageWho::RUSAGE_CHILDREN)
+        .map(|usage| {
+            (usage.user_time().num_microseconds() + usage.system_time().num_microseconds()) as u64
+        })
+        .unwrap_or(0)
+}
+
+/// How long the sample collector waits for the next sample before it checks whether the child is done
+const SAMPLE_POLL: Duration = Duration::from_millis(10);
+
+/// Reads the samples while the child runs, it blocks in `send` once the socket buffer is full.
+/// Returns everything the child has measured once `done` is set, also after it was killed.
+fn collect_samples(samples: IpcReceiver<u64>, done: Arc<AtomicBool>) -> JoinHandle<Vec<u64>> {
+    thread::spawn(move || {
+        let mut collected = Vec::new();
+
+        loop {
+            match samples.try_recv_timeout(SAMPLE_POLL) {
+                Ok(sample) => collected.push(sample),
+                Err(TryRecvError::Empty) if !done.load(Ordering::SeqCst) => continue,
+                // the child is gone and everything it sent has been read
+                Err(_) => break,
+            }
+        }
+
+        collected
+    })
+}
+
+/// Turns a child that didn't report back into a `TestError`
+fn child_error(err: SpawnError, pid: u32) -> TestError {
+    if let Some(panic) = err.panic_info() {
+        let message = match panic.location() {
+            Some(location) => format!(
+                "{} at {}:{}:{}",
+                panic.message(),
+                location.file(),
+                location.line(),
+                location.column()
+            ),
+            None => panic.message().to_string(),
+        };
+
+        return TestError::Crash(message);
+    }
+
+    if err.is_remote_close() {
+        // procspawn doesn't wait for the child if it vanished, so its exit status is still there for us
+        match waitpid(Pid::from_raw(pid as i32), None) {
+            Ok(WaitStatus::Signaled(_, signal, _)) => return TestError::Signal(signal as i32),
+            Ok(WaitStatus::Exited(_, code)) => {
+                return TestError::Crash(format!("exited with code {}", code))
+            }
+            _ => {}
+        }
+    }
+
+    TestError::Crash(err.to_string())
+}
+
+/// Tells apart engines that died because they hit one of their limits, `cpu_time` being the
+/// microseconds of CPU time the engine used
+fn limit_error(err: TestError, limits: &Limits, cpu_time: u64) -> TestError {
+    const SIGXCPU: i32 = Signal::SIGXCPU as i32;
+    const SIGKILL: i32 = Signal::SIGKILL as i32;
+
+    let cpu_exceeded = limits
+        .cpu_time
+        .is_some_and(|seconds| cpu_time >= seconds.saturating_mul(1_000_000));
+
+    match err {
+        // the kernel sends SIGKILL once the hard CPU limit is reached too, but so does the OOM killer
+        TestError::Signal(SIGXCPU | SIGKILL) if cpu_exceeded => TestError::CpuLimit,
+        // an abort doesn't tell why, only engines that throw say they ran out of memory
+        TestError::Script(err)
+            if limits.address_space.is_some()
+                && err.message.to_lowercase().contains("out of memory") =>
+        {
+            TestError::OutOfMemory
+        }
+        err => err,
+    }
+}
+
+/// Runs `source` with `run_file` in a procspawn child, while monitoring its resources and validating what it did.
+/// Relative paths of the validator's files are looked up in `files_root`, the working directory if `None`.
+/// `procspawn::init` has to be called at the start of `main`, the child runs everything before it again.
+/// A child still running at the timeout is killed, engines that can't interrupt a script rely on that.
+pub(crate) fn run<S: Serialize + DeserializeOwned>(
+    path: &Path,
+    validator: &Validator,
+    engine: Engine,
+    source: S,
+    run_file: fn((S, Runs)) -> ChildResult,
+    files_root: Option<&Path>,
+) -> Result<TestResult, TestError> {
+    let mut res = TestResult::new(path, engine);
+
+    let (samples, samples_rx) = ipc::channel()?;
+    let done = Arc::new(AtomicBool::new(false));
+    let collector = collect_samples(samples_rx, Arc::clone(&done));
+    let timeout = validator.timeout.unwrap_or(DEFAULT_TIMEOUT);
+
+    let runs = if !validator.http.is_empty() {
+        Runs {
+            reruns: 1,
+            warmup: 0,
+            samples,
+            timeout,
+        }
+    } else {
+        Runs {
+            reruns: validator.reruns,
+            warmup: validator.warmup,
+            samples,
+            timeout,
+        }
+    };
+
+    let cpu_start = children_cpu_time();
+
+    let mut builder = procspawn::Builder::new();
+    validator
+        .limits
+        .pre_exec_hook(&mut builder, procspawn::Builder::pre_exec::<Hook>);
+
+    let mut h = builder.spawn((source, runs), run_file);
+
+    let start = Instant::now();
+    let pid = h.pid().unwrap();
+
+    let monitor = ResourceMonitor::new(pid);
+    let monitor = Arc::new(monitor);
+
+    let handle = {
+        let monitor = Arc::clone(&monitor);
+        thread::spawn(move || {
+            monitor.start(&start);
+        })
+    };
+
+    let failure = if !validator.http.is_empty() {
+        let monitor = Arc::clone(&monitor);
+        let http_res = validator.validate_http(&monitor);
+        h.kill().ok();
+        res.time = start.elapsed().as_micros() as u64;
+
+        match http_res {
+            Ok(http_res) => {
+                res.http = Some(http_res);
+                None
+            }
+            Err(err) => Some(err),
+        }
+    } else {
+        match h.join_timeout(timeout + INTERRUPT_GRACE) {
+            Ok(Ok(output)) => {
+                let phases = &output.phases;
+                res.init_time = phases.init;
+                res.context_time = phases.context;
+                res.compile_time = phases.compile;
+                res.instantiate_time = phases.instantiate;
+                res.run_time = phases.run;
+                res.time =
+                    phases.init + phases.context + phases.compile + phases.instantiate + phases.run;
+                res.console = output.console.lines().map(|l| l.to_string()).collect();
+
+                if !validator.console.is_empty() {
+                    res.console_valid = Some(validator.validate_console(output.console).result);
+                }
+
+                None
+            }
+            Ok(Err(ChildError::Script(err))) => Some(TestError::Script(err)),
+            Ok(Err(ChildError::Engine(err))) => Some(TestError::Crash(err)),
+            Ok(Err(ChildError::Timeout)) => {
+                res.time = start.elapsed().as_micros() as u64;
+                Some(TestError::Timeout(timeout))
+            }
+            Err(err) if err.is_timeout() => {
+                h.kill().ok();
+                res.time = start.elapsed().as_micros() as u64;
+                Some(TestError::Timeout(timeout))
+            }
+            Err(err) => Some(child_error(err, pid)),
+        }
+    };
+
+    done.store(true, Ordering::SeqCst);
+    res.samples = collector.join().unwrap();
+    res.stats = Stats::from_samples(&res.samples);
+
+    let monitor = Arc::clone(&monitor);
+    monitor.stop(); //hopefully we can lock this shit, while the thread is obviously running... Else it will explode...
+
+    handle.join().unwrap();
+
+    res.resources = monitor.resources.read().unwrap().to_vec();
+
+    res.cpu_time = children_cpu_time().saturating_sub(cpu_start);
+
+    conclude(res, failure, validator, files_root)
+}
+
+/// Validates the files the engine wrote and decides the outcome of the run, `failure` being why
+/// the engine didn't finish
+pub(crate) fn conclude(
+    mut res: TestResult,
+    failure: Option<TestError>,
+    validator: &Validator,
+    files_root: Option<&Path>,
+) -> Result<TestResult, TestError> {
+    if !validator.files.is_empty() {
+        let files = match files_root {
+            Some(root) => validator.validate_files_in(root),
+            None => validator.validate_files(),
+        };
+        res.files_valid = Some(files.iter().all(|file| file.result));
+    }
+
+    let valid = res.console_valid.unwrap_or(true)
+        && res.files_valid.unwrap_or(true)
+        && res
+            .http
+            .iter()
+            .flatten()
+            .all(|http| !matches!(http.result, HTTPResultType::Fail));
+
+    res.outcome = match failure {
+        Some(err) => Outcome::try_from(limit_error(err, &validator.limits, res.cpu_time))?,
+        None if valid => Outcome::Passed,
+        None => Outcome::Failed,
+    };
+    res.success = matches!(res.outcome, Outcome::Passed);
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(cpu_time: Option<u64>, address_space: Option<u64>) -> Limits {
+        Limits {
+            cpu_time,
+            address_space,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn kills_within_the_cpu_limit_stay_signals() {
+        let kill = || TestError::Signal(Signal::SIGKILL as i32);
+
+        assert!(matches!(
+            limit_error(kill(), &limits(Some(2), None), 2_500_000),
+            TestError::CpuLimit
+        ));
+        assert!(matches!(
+            limit_error(kill(), &limits(Some(2), None), 300_000),
+            TestError::Signal(_)
+        ));
+        assert!(matches!(
+            limit_error(kill(), &limits(None, None), 2_500_000),
+            TestError::Signal(_)
+        ));
+    }
+
+    #[test]
+    fn aborts_are_not_out_of_memory() {
+        let abort = TestError::Signal(Signal::SIGABRT as i32);
+        assert!(matches!(
+            limit_error(abort, &limits(None, Some(1 << 20)), 0),
+            TestError::Signal(_)
+        ));
+
+        let thrown = TestError::Script(ScriptError {
+            message: "InternalError: out of memory".to_string(),
+            ..Default::default()
+        });
+        assert!(matches!(
+            limit_error(thrown, &limits(None, Some(1 << 20)), 0),
+            TestError::OutOfMemory
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// Summary of the per-iteration samples of a run, all values in nanoseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub min: u64,
+    pub max: u64,
+    pub median: f64,
+    pub mean: f64,
+    pub p95: u64,
+    pub p99: u64,
+    pub stddev: f64,
+    /// Coefficient of variation, `stddev / mean`
+    pub cv: f64,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[u64]) -> Option<Stats> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let len = sorted.len();
+
+        let median = if len % 2 == 0 {
+            (sorted[len / 2 - 1] as f64 + sorted[len / 2] as f64) / 2.0
+        } else {
+            sorted[len / 2] as f64
+        };
+
+        let mean = sorted.iter().map(|s| *s as f64).sum::<f64>() / len as f64;
+
+        // sample standard deviation, a single sample has no spread
+        let stddev = if len > 1 {
+            let variance = sorted
+                .iter()
+                .map(|s| (*s as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (len - 1) as f64;
+
+            variance.sqrt()
+        } else {
+            0.0
+        };
+
+        let cv = if mean > 0.0 { stddev / mean } else { 0.0 };
+
+        Some(Stats {
+            min: sorted[0],
+            max: sorted[len - 1],
+            median,
+            mean,
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+            stddev,
+            cv,
+        })
+    }
+}
+
+/// Nearest-rank percentile of already sorted samples
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_samples_have_no_stats() {
+        assert!(Stats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn a_single_sample_has_no_spread() {
+        let stats = Stats::from_samples(&[42]).unwrap();
+
+        assert_eq!((stats.min, stats.max), (42, 42));
+        assert_eq!((stats.p95, stats.p99), (42, 42));
+        assert_eq!(stats.median, 42.0);
+        assert_eq!(stats.mean, 42.0);
+        assert_eq!(stats.stddev, 0.0);
+        assert_eq!(stats.cv, 0.0);
+    }
+
+    #[test]
+    fn even_counts_average_the_middle_samples() {
+        let stats = Stats::from_samples(&[4, 1, 3, 2]).unwrap();
+
+        assert_eq!(stats.median, 2.5);
+        assert_eq!((stats.min, stats.max), (1, 4));
+    }
+
+    #[test]
+    fn percentiles_and_spread() {
+        let samples = (1..=20).rev().collect::<Vec<u64>>();
+        let stats = Stats::from_samples(&samples).unwrap();
+
+        assert_eq!(stats.p95, 19);
+        assert_eq!(stats.p99, 20);
+        assert_eq!(stats.mean, 10.5);
+        // the sample variance of 1..=n is n(n + 1) / 12
+        let stddev = 35f64.sqrt();
+        assert!((stats.stddev - stddev).abs() < 1e-9);
+        assert!((stats.cv - stddev / 10.5).abs() < 1e-9);
+    }
+}
@@ -1,24 +1,123 @@
+use std::fmt::{Display, Formatter};
 use std::path::Path;
+use std::time::Duration;
 
-use crate::errors::TestError;
-use crate::js::JSEngine;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ScriptError, TestError};
+use crate::js::{JSEngine, JSRunner};
 use crate::resources::ResourceUsage;
-use crate::validator::HTTPResult;
-use crate::wasm::WasmEngine;
+use crate::stats::Stats;
+use crate::validator::{HTTPResult, Validator};
+use crate::wasm::{self, WasmEngine, WasmRunner};
+use crate::Test;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Engine {
     Wasm(WasmEngine),
     JS(JSEngine),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Outcome {
+    /// The script ran and every validator passed
+    Passed,
+    /// The script ran, but at least one validator failed
+    Failed,
+    ScriptError(ScriptError),
+    Crashed(String),
+    Signaled(i32),
+    /// The engine was killed after the timeout, only the samples measured until then are recorded
+    TimedOut,
+    /// The engine hit the address space limit
+    OutOfMemory,
+    /// The engine used up its CPU time limit
+    CpuLimit,
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::JS(JSEngine::External(name)) => write!(f, "{}", name),
+            Engine::JS(engine) => write!(f, "{:?}", engine),
+            Engine::Wasm(engine) => write!(f, "{:?} (wasm)", engine),
+        }
+    }
+}
+
+impl Outcome {
+    /// Short machine readable name of the outcome, without any details
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Outcome::Passed => "passed",
+            Outcome::Failed => "failed",
+            Outcome::ScriptError(_) => "script_error",
+            Outcome::Crashed(_) => "crashed",
+            Outcome::Signaled(_) => "signaled",
+            Outcome::TimedOut => "timed_out",
+            Outcome::OutOfMemory => "out_of_memory",
+            Outcome::CpuLimit => "cpu_limit",
+        }
+    }
+
+    /// Details of a failed run, empty if there is nothing more to say than `kind`
+    pub fn details(&self) -> String {
+        match self {
+            Outcome::ScriptError(err) => err.to_string(),
+            Outcome::Crashed(err) => err.clone(),
+            Outcome::Signaled(signal) => format!("signal {}", signal),
+            _ => String::new(),
+        }
+    }
+}
+
+impl TryFrom<TestError> for Outcome {
+    type Error = TestError;
+
+    /// Records engine failures, everything else is a harness error and is handed back
+    fn try_from(err: TestError) -> Result<Self, Self::Error> {
+        match err {
+            TestError::Script(err) => Ok(Outcome::ScriptError(err)),
+            TestError::Crash(err) => Ok(Outcome::Crashed(err)),
+            TestError::Signal(signal) => Ok(Outcome::Signaled(signal)),
+            TestError::Timeout(_) => Ok(Outcome::TimedOut),
+            TestError::OutOfMemory => Ok(Outcome::OutOfMemory),
+            TestError::CpuLimit => Ok(Outcome::CpuLimit),
+            err => Err(err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestResult {
     pub path: Box<Path>,
+    /// Wall time of all phases in microseconds
     pub time: u64,
+    /// Creating the engine / runtime / isolate in microseconds
+    pub init_time: u64,
+    /// Creating the context and registering globals in microseconds
+    pub context_time: u64,
+    /// Parsing and compiling the script in microseconds,
+    /// 0 for engines that only compile as part of running the script
+    pub compile_time: u64,
+    /// Instantiating the wasm module in microseconds, 0 for JS
+    pub instantiate_time: u64,
+    /// Running the script, including warmup, in microseconds
+    pub run_time: u64,
+    /// CPU time (user + system) of the engine process in microseconds
     pub cpu_time: u64,
+    /// Duration of every measured iteration in nanoseconds, warmup excluded
+    pub samples: Vec<u64>,
+    pub stats: Option<Stats>,
     pub resources: Vec<ResourceUsage>,
     pub success: bool,
+    /// Why the run did or didn't succeed
+    pub outcome: Outcome,
+    pub console: Vec<String>,
+    /// `None` if the validator doesn't expect any console output
+    pub console_valid: Option<bool>,
+    /// `None` if the validator doesn't expect any files
+    pub files_valid: Option<bool>,
     pub http: Option<Vec<HTTPResult>>,
     pub engine: Engine,
 }
@@ -28,43 +127,112 @@ impl TestResult {
         TestResult {
             path: Box::from(path),
             time: 0,
+            init_time: 0,
+            context_time: 0,
+            compile_time: 0,
+            instantiate_time: 0,
+            run_time: 0,
             cpu_time: 0,
+            samples: vec![],
+            stats: None,
             resources: vec![],
             success: false,
+            outcome: Outcome::Passed,
+            console: vec![],
+            console_valid: None,
+            files_valid: None,
             http: None,
             engine,
         }
     }
 }
 
+/// Overrides for every test of a run, `None` keeps what the manifest says
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunOptions {
+    pub reruns: Option<u32>,
+    pub warmup: Option<u32>,
+    /// Only used for tests whose manifest doesn't set a timeout
+    pub timeout: Option<Duration>,
+}
+
+impl RunOptions {
+    fn apply(&self, validator: &mut Validator) {
+        if let Some(reruns) = self.reruns {
+            validator.reruns = reruns;
+        }
+
+        if let Some(warmup) = self.warmup {
+            validator.warmup = warmup;
+        }
+
+        if validator.timeout.is_none() {
+            validator.timeout = self.timeout;
+        }
+    }
+}
+
+/// The engines of a run, `.js` files are run by the JS runners and `.wasm` and `.wat` files by
+/// the wasm runners
+#[derive(Default)]
+pub struct Runners {
+    pub js: Vec<Box<dyn JSRunner>>,
+    pub wasm: Vec<Box<dyn WasmRunner>>,
+}
+
+impl Runners {
+    pub fn is_empty(&self) -> bool {
+        self.js.is_empty() && self.wasm.is_empty()
+    }
+}
+
+/// Runs every test under `path` with every engine that is compiled in,
+/// returning one `TestResult` per engine and script.
+/// The engines run in procspawn children, so `procspawn::init` has to be called first thing in `main`.
 pub fn test(path: &Path) -> Result<Vec<TestResult>, TestError> {
+    let test = Test::new();
+    let mut runners = Runners {
+        js: test.js_runners()?,
+        wasm: test.wasm_runners()?,
+    };
+
+    test_with(path, &mut runners, &RunOptions::default())
+}
+
+/// Runs every test under `path` with the given runners, `procspawn::init` has to be called
+/// first thing in `main` like for `test`
+pub fn test_with(
+    path: &Path,
+    runners: &mut Runners,
+    options: &RunOptions,
+) -> Result<Vec<TestResult>, TestError> {
     if path.is_dir() {
-        test_dir(path)
+        test_dir(path, runners, options)
     } else {
-        let res = test_file(path)?;
-        Ok(vec![res])
+        test_file(path, runners, options)
     }
 }
 
-fn test_dir(path: &Path) -> Result<Vec<TestResult>, TestError> {
+fn test_dir(
+    path: &Path,
+    runners: &mut Runners,
+    options: &RunOptions,
+) -> Result<Vec<TestResult>, TestError> {
     let mut results = vec![];
 
     for entry in path.read_dir()? {
         let entry = entry?;
         let path = entry.path();
         if path.is_dir() {
-            let mut res = test_dir(&path)?;
+            let mut res = test_dir(&path, runners, options)?;
             results.append(&mut res);
         } else {
-            let res = test_file(&path);
-            if let Ok(res) = res {
-                results.push(res);
-            } else {
-                match res.err().unwrap() {
-                    TestError::Other(err) => return Err(TestError::Other(err)),
-                    TestError::AlreadyInitialized => return Err(TestError::AlreadyInitialized),
-                    _ => continue,
-                }
+            match test_file(&path, runners, options) {
+                Ok(mut res) => results.append(&mut res),
+                // manifests and everything else that isn't a test
+                Err(TestError::InvalidFileType) => continue,
+                // a broken manifest or module would otherwise silently drop the test from the run
+                Err(err) => return Err(err),
             }
         }
     }
@@ -72,6 +240,49 @@ fn test_dir(path: &Path) -> Result<Vec<TestResult>, TestError> {
     Ok(results)
 }
 
-fn test_file(_path: &Path) -> Result<TestResult, TestError> {
-    todo!()
+/// Engine failures end up in the results, harness errors like a broken manifest or a runner that
+/// couldn't start are returned
+fn test_file(
+    path: &Path,
+    runners: &mut Runners,
+    options: &RunOptions,
+) -> Result<Vec<TestResult>, TestError> {
+    if !path.is_file() {
+        return Err(TestError::IsDir);
+    }
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        // a file no engine would run isn't looked at, so it can't fail the run
+        Some("js") if runners.js.is_empty() => Ok(vec![]),
+        Some("wasm" | "wat") if runners.wasm.is_empty() => Ok(vec![]),
+        Some("js") => {
+            let mut validator = Validator::for_script(path)?.unwrap_or_default();
+            options.apply(&mut validator);
+            let mut results = Vec::with_capacity(runners.js.len());
+
+            for runner in runners.js.iter_mut() {
+                results.push(runner.run_js_file(path, &validator)?);
+            }
+
+            Ok(results)
+        }
+        Some("wasm" | "wat") => {
+            let mut validator = Validator::for_script(path)?.unwrap_or_default();
+            options.apply(&mut validator);
+            let wasm = wasm::read_module(path)?;
+            let mut results = Vec::with_capacity(runners.wasm.len());
+
+            for runner in runners.wasm.iter_mut() {
+                match runner.run_wasm(path, &wasm, &validator) {
+                    Ok(res) => results.push(res),
+                    // like a WASI module on an engine without a WASI host
+                    Err(TestError::Unsupported(_)) => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Ok(results)
+        }
+        _ => Err(TestError::InvalidFileType),
+    }
 }
@@ -0,0 +1,8 @@
+use std::io::Write;
+
+/// Writes `content` to a temporary file ending in `suffix`, it is deleted when dropped
+pub(crate) fn temp_file(suffix: &str, content: &[u8]) -> tempfile::NamedTempFile {
+    let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+    file.write_all(content).unwrap();
+    file
+}
@@ -1,15 +1,24 @@
+use std::path::Path;
 use std::str::FromStr;
 use std::thread;
 use std::time::Duration;
 
 use reqwest::blocking::RequestBuilder as BlockingRequestBuilder;
-use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
 
 use crate::benchmark::{benchmark, benchmark_no_validate};
 use crate::errors::TestError;
+use crate::limits::Limits;
 use crate::resources::ResourceMonitor;
 
+/// How long a test may run if its validator doesn't set a timeout
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Export that is called when the validator doesn't name one, the WASI entry point
+pub const DEFAULT_ENTRY: &str = "_start";
+
 /// # Validator
 /// Validate results
 /// - Validate by created files
@@ -19,9 +28,24 @@ use crate::resources::ResourceMonitor;
 
 pub struct Validator {
     pub files: Vec<File>,
-    pub console: Vec<String>, //TODO
+    pub console: Vec<String>,
     pub http: Vec<HTTP>,
     pub reruns: u32,
+    /// Iterations run before the measured ones
+    pub warmup: u32,
+    /// Wall-clock limit for the whole run, `None` uses [`DEFAULT_TIMEOUT`]
+    pub timeout: Option<Duration>,
+    /// Resource limits of the engine process
+    pub limits: Limits,
+    /// Exported function of a wasm module that is run, `None` uses [`DEFAULT_ENTRY`]
+    pub entry: Option<String>,
+    /// Fuel a wasm module gets for the whole run, on engines that support metering
+    pub fuel: Option<u64>,
+    /// Runs a wasm module with WASI, its stdout is the console and `files` are looked up in its
+    /// sandbox directory
+    pub wasi: bool,
+    /// Arguments of a WASI module, after its file name
+    pub args: Vec<String>,
 }
 
 pub struct File {
@@ -66,7 +90,7 @@ pub struct ConsoleResult<'a> {
     pub res_individually: Vec<bool>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HTTPResult {
     pub index: usize,
     pub result: HTTPResultType,
@@ -74,7 +98,7 @@ pub struct HTTPResult {
     pub response_code: Option<u16>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum HTTPResultType {
     Success,
     Partial,
@@ -89,14 +113,33 @@ impl Validator {
             console: vec![],
             http: vec![],
             reruns: 1,
+            warmup: 0,
+            timeout: None,
+            limits: Limits::default(),
+            entry: None,
+            fuel: None,
+            wasi: false,
+            args: vec![],
         }
     }
 
     pub fn validate_files(&self) -> Vec<FileResult> {
+        self.validate_files_in(Path::new(""))
+    }
+
+    /// Validates the files with relative paths looked up in `root`
+    pub fn validate_files_in(&self, root: &Path) -> Vec<FileResult> {
         let mut results = vec![];
 
         for file in &self.files {
-            let output_bytes = std::fs::read(&file.path).unwrap();
+            let Ok(output_bytes) = std::fs::read(root.join(&file.path)) else {
+                results.push(FileResult {
+                    file,
+                    sha256: String::new(),
+                    result: false,
+                });
+                continue;
+            };
 
             let checksum = sha256::digest(&output_bytes);
             let result = checksum == file.sha256;
@@ -108,19 +151,6 @@ impl Validator {
             });
         }
 
-        let mut success = true;
-
-        for result in &results {
-            if !result.result {
-                success = false;
-                println!("File {} failed", result.file.path);
-            }
-        }
-
-        if success {
-            println!("All files passed");
-        }
-
         results
     }
 
@@ -160,9 +190,7 @@ impl Validator {
 
             let mut headers = HeaderMap::new();
             for header in &http.headers {
-                let mut split = header.split(':');
-                let key = HeaderName::from_str(split.next().unwrap()).unwrap();
-                let value = split.next().to_owned().unwrap().parse().unwrap();
+                let (key, value) = parse_header(header).map_err(TestError::String)?;
 
                 headers.insert(key, value);
             }
@@ -252,3 +280,17 @@ fn check(
 
     (code == response_code && text == response, code, text)
 }
+
+/// Parses a `Key: Value` header, everything after the first `:` is the value
+pub(crate) fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (key, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("header `{}` is not in the form `Key: Value`", header))?;
+
+    let key = HeaderName::from_str(key.trim())
+        .map_err(|_| format!("header `{}` has an invalid name", header))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| format!("header `{}` has an invalid value", header))?;
+
+    Ok((key, value))
+}
@@ -1,34 +1,128 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::TestError;
 use crate::validator::Validator;
 use crate::TestResult;
-use std::error::Error;
 
-mod deno;
-mod javascriptcore;
-mod spidermonkey;
-mod v8;
+pub(crate) mod deno;
+pub(crate) mod javascriptcore;
+mod runner;
+
+#[cfg(feature = "mozjs")]
+pub(crate) mod spidermonkey;
+
+#[cfg(feature = "v8")]
+pub(crate) mod v8;
 mod wasmedge;
-mod wasmer;
-mod wasmi;
-mod wasmtime;
+pub(crate) mod wasmer;
+pub(crate) mod wasmi;
+pub(crate) mod wasmtime;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum WasmEngine {
-    Wasmer,         //https://github.com/wasmerio/wasmer
-    Wasmtime,       //https://github.com/bytecodealliance/wasmtime
-    Wamr,           //https://github.com/bytecodealliance/wasm-micro-runtime
-    WasmEdge,       //https://github.com/WasmEdge/WasmEdge
-    Wasmi,          //https://github.com/paritytech/wasmi
-    SpiderMonkey,   //https://spidermonkey.dev/
-    V8,             //https://v8.dev/
-    JavaScriptCore, //https://developer.apple.com/documentation/javascriptcore
-    Deno,           //https://github.com/denoland/deno does deno really support wasm?
+    Wasmer(WasmerCompiler), //https://github.com/wasmerio/wasmer
+    Wasmtime,               //https://github.com/bytecodealliance/wasmtime
+    Wamr,                   //https://github.com/bytecodealliance/wasm-micro-runtime
+    WasmEdge,               //https://github.com/WasmEdge/WasmEdge
+    Wasmi,                  //https://github.com/paritytech/wasmi
+    SpiderMonkey,           //https://spidermonkey.dev/
+    V8,                     //https://v8.dev/
+    JavaScriptCore,         //https://developer.apple.com/documentation/javascriptcore
+    Deno,                   //https://github.com/denoland/deno does deno really support wasm?
+}
+
+impl WasmEngine {
+    /// Whether the engine can run modules with WASI, the JS engines have no WASI host
+    pub fn supports_wasi(&self) -> bool {
+        matches!(self, WasmEngine::Wasmtime | WasmEngine::Wasmer(_))
+    }
+}
+
+/// Compiler backend of Wasmer, they trade compile latency for peak throughput
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WasmerCompiler {
+    /// Fast to compile, no optimizations
+    Singlepass,
+    Cranelift,
+    /// Slow to compile, best code, needs the `wasmer-llvm` feature
+    LLVM,
 }
 
 pub trait WasmRunner {
-    fn init() -> Result<(), Box<dyn Error>>;
-    fn run_wasm_file(
-        &self,
-        path: &str,
-        validator: &Validator,
-    ) -> Result<TestResult, Box<dyn Error>>;
+    /// Runs a `.wasm` or `.wat` file, prefer `run_wasm` to run one file on several engines, so
+    /// it's only read and assembled once
+    fn run_wasm_file<'a>(
+        &'a mut self,
+        path: &Path,
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        let wasm = read_module(path)?;
+        self.run_wasm(path, &wasm, validator)
+    }
+
+    /// Runs `wasm`, the binary module of the file at `path`
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError>;
+}
+
+/// Reads the binary module of a `.wasm` file or assembles a `.wat` file
+pub fn read_module(path: &Path) -> Result<Vec<u8>, TestError> {
+    if !path.is_file() {
+        return Err(TestError::IsDir);
+    }
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("wasm") => Ok(fs::read(path)?),
+        // the error renders the file, line and column together with the offending source line
+        Some("wat") => wat::parse_file(path).map_err(|err| TestError::Wat(err.to_string())),
+        _ => Err(TestError::InvalidFileType),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_file;
+
+    #[test]
+    fn assembles_wat() {
+        let file = temp_file(".wat", b"(module (func (export \"_start\")))");
+
+        let wasm = read_module(file.path()).ok().unwrap();
+        assert_eq!(&wasm[..4], b"\0asm");
+    }
+
+    #[test]
+    fn wat_errors_point_at_the_line_and_column() {
+        let file = temp_file(
+            ".wat",
+            b"(module\n  (func (export \"_start\")\n    i32.bogus))",
+        );
+
+        let Err(TestError::Wat(err)) = read_module(file.path()) else {
+            panic!("the module should not assemble");
+        };
+        assert!(
+            err.contains(&format!("{}:3:5", file.path().display())),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let file = temp_file(".txt", b"(module)");
+
+        assert!(matches!(
+            read_module(file.path()),
+            Err(TestError::InvalidFileType)
+        ));
+    }
 }
@@ -1 +1,79 @@
-pub struct Deno {}
+use std::path::Path;
+
+use deno_core::v8::{self, TryCatch};
+use deno_core::ModuleCode;
+
+use crate::errors::TestError;
+use crate::js::deno::{caught, script_error, Deno as JsDeno};
+use crate::validator::Validator;
+use crate::wasm::runner::{repeat, run, PhaseTimer, WebAssemblyScripts};
+use crate::wasm::{WasmEngine, WasmRunner};
+use crate::{Engine, TestResult};
+
+/// Wasm through the `WebAssembly` API of the V8 embedded in Deno, this measures what Deno adds on
+/// top of plain V8
+pub struct Deno;
+
+impl Deno {
+    pub fn new() -> Self {
+        Deno
+    }
+}
+
+impl Default for Deno {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmRunner for Deno {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::Deno),
+            |(module, runs)| {
+                let scripts = WebAssemblyScripts::new(&module);
+
+                let mut timer = PhaseTimer::start();
+
+                let mut runtime = JsDeno::runtime();
+                timer.init();
+
+                runtime
+                    .execute_script("load", ModuleCode::from(scripts.load))
+                    .map_err(script_error)?;
+                timer.context();
+
+                runtime
+                    .execute_script("compile", ModuleCode::from_static(scripts.compile))
+                    .map_err(script_error)?;
+                timer.compile();
+
+                runtime
+                    .execute_script("instantiate", ModuleCode::from(scripts.instantiate))
+                    .map_err(script_error)?;
+
+                let scope = &mut runtime.handle_scope();
+                let tc = &mut TryCatch::new(scope);
+
+                let code = v8::String::new(tc, scripts.call).unwrap();
+                let Some(call) = v8::Script::compile(tc, code, None) else {
+                    return Err(caught(tc).into());
+                };
+                timer.instantiate();
+
+                repeat(runs, timer, || match call.run(tc) {
+                    Some(_) => Ok(()),
+                    None => Err(caught(tc)),
+                })
+            },
+        )
+    }
+}
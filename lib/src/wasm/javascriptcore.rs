@@ -1 +1,83 @@
-pub struct JavaScriptCore {}
+use std::path::Path;
+
+use javascriptcore::{Context, ContextExt, ValueExt};
+
+use crate::errors::{ScriptError, TestError};
+use crate::js::javascriptcore::JavaScriptCore as JsJavaScriptCore;
+use crate::validator::Validator;
+use crate::wasm::runner::{no_export, repeat, run, PhaseTimer, WebAssemblyScripts};
+use crate::wasm::{WasmEngine, WasmRunner};
+use crate::{Engine, TestResult};
+
+/// Wasm through the `WebAssembly` API of JavaScriptCore, which starts the module in its LLInt
+/// interpreter and tiers up to BBQ and OMG
+pub struct JavaScriptCore;
+
+impl JavaScriptCore {
+    pub fn new() -> Self {
+        JavaScriptCore
+    }
+
+    fn evaluate(context: &Context, code: &str) -> Result<(), ScriptError> {
+        context.evaluate(code);
+        Self::thrown(context)
+    }
+
+    /// Whether the last evaluation or call threw
+    fn thrown(context: &Context) -> Result<(), ScriptError> {
+        match JsJavaScriptCore::script_error(context) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for JavaScriptCore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmRunner for JavaScriptCore {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::JavaScriptCore),
+            |(module, runs)| {
+                let scripts = WebAssemblyScripts::new(&module);
+
+                let mut timer = PhaseTimer::start();
+
+                let context = JsJavaScriptCore::context();
+                timer.init();
+
+                Self::evaluate(&context, &scripts.load)?;
+                timer.context();
+
+                Self::evaluate(&context, scripts.compile)?;
+                timer.compile();
+
+                Self::evaluate(&context, &scripts.instantiate)?;
+
+                // called directly, so no iteration parses a script
+                let entry = context
+                    .value("__wasm_entry")
+                    .filter(|entry| entry.is_function())
+                    .ok_or_else(|| no_export(&module.entry))?;
+                timer.instantiate();
+
+                repeat(runs, timer, || {
+                    let _ = entry.function_callv(&[]);
+                    Self::thrown(&context)
+                })
+            },
+        )
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ScriptError, TestError};
+pub(super) use crate::runner::{repeat, ChildError, ChildResult, PhaseTimer, Runs};
+use crate::validator::{Validator, DEFAULT_ENTRY};
+use crate::{Engine, TestResult};
+
+/// What the engine child needs to run a wasm module
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct WasmModule {
+    pub bytes: Vec<u8>,
+    /// Exported function that is called in every iteration
+    pub entry: String,
+    /// Fuel for the whole run, `None` disables metering
+    pub fuel: Option<u64>,
+    /// `None` runs the module without WASI imports
+    pub wasi: Option<Wasi>,
+}
+
+/// The WASI environment of a module
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Wasi {
+    /// `argv`, starting with the file name of the module
+    pub args: Vec<String>,
+    /// Directory on the host that is preopened as `.`, the module can't reach anything outside
+    pub dir: PathBuf,
+}
+
+/// An error of the module that is only a message, for engines whose errors carry nothing else
+pub(super) fn error(err: impl ToString) -> ChildError {
+    ChildError::Script(ScriptError {
+        message: err.to_string(),
+        ..Default::default()
+    })
+}
+
+/// The module doesn't export `entry`, or not as a function
+pub(super) fn no_export(entry: &str) -> ChildError {
+    error(format!("Module has no exported function `{}`", entry))
+}
+
+/// Runs `wasm`, the binary module read from `path`, with `run_module` in a procspawn child
+/// A module that needs WASI is `Unsupported` on engines without a WASI host, nothing is spawned
+pub(super) fn run(
+    path: &Path,
+    wasm: &[u8],
+    validator: &Validator,
+    engine: Engine,
+    run_module: fn((WasmModule, Runs)) -> ChildResult,
+) -> Result<TestResult, TestError> {
+    let supported = match &engine {
+        Engine::Wasm(engine) => engine.supports_wasi(),
+        Engine::JS(_) => false,
+    };
+
+    if validator.wasi && !supported {
+        return Err(TestError::Unsupported(format!(
+            "{} has no WASI host",
+            engine
+        )));
+    }
+
+    // every run gets a fresh directory, so files of earlier engines can't make it pass
+    let sandbox = if validator.wasi {
+        Some(
+            tempfile::Builder::new()
+                .prefix("speed-test-wasi-")
+                .tempdir()?,
+        )
+    } else {
+        None
+    };
+
+    let mut args = vec![path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()];
+    args.extend(validator.args.iter().cloned());
+
+    let module = WasmModule {
+        bytes: wasm.to_vec(),
+        entry: validator
+            .entry
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ENTRY.to_string()),
+        fuel: validator.fuel,
+        wasi: sandbox.as_ref().map(|dir| Wasi {
+            args,
+            dir: dir.path().to_path_buf(),
+        }),
+    };
+
+    // the sandbox is removed when it is dropped, after its files were validated
+    crate::runner::run(
+        path,
+        validator,
+        engine,
+        module,
+        run_module,
+        sandbox.as_ref().map(|dir| dir.path()),
+    )
+}
+
+/// Scripts that drive a module through the `WebAssembly` API of a JS engine. They are evaluated
+/// one after another, so the phases can be timed like those of a standalone runtime.
+pub(super) struct WebAssemblyScripts {
+    /// Puts the bytes into `globalThis.__wasm_bytes`, part of the context phase
+    pub load: String,
+    /// Compiles `__wasm_bytes` to `globalThis.__wasm_module`
+    pub compile: &'static str,
+    /// Instantiates `__wasm_module` and puts the entry into `globalThis.__wasm_entry`
+    pub instantiate: String,
+    /// Calls `__wasm_entry`, once per iteration
+    pub call: &'static str,
+}
+
+impl WebAssemblyScripts {
+    pub fn new(module: &WasmModule) -> Self {
+        // a hex string is much cheaper to parse than an array literal of the same bytes
+        let mut hex = String::with_capacity(module.bytes.len() * 2);
+        for byte in &module.bytes {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+
+        let load = format!(
+            r#"
+globalThis.__wasm_bytes = ((hex) => {{
+    const bytes = new Uint8Array(hex.length / 2);
+    for (let i = 0; i < bytes.length; i++) {{
+        bytes[i] = parseInt(hex.substr(i * 2, 2), 16);
+    }}
+    return bytes;
+}})("{}");
+"#,
+            hex
+        );
+
+        let entry = serde_json::to_string(&module.entry).expect("a string is valid JSON");
+
+        let instantiate = format!(
+            r#"
+globalThis.__wasm_instance = new WebAssembly.Instance(__wasm_module, {{}});
+globalThis.__wasm_entry = __wasm_instance.exports[{entry}];
+if (typeof __wasm_entry !== "function") {{
+    throw new Error("Module has no exported function `" + {entry} + "`");
+}}
+"#,
+            entry = entry
+        );
+
+        WebAssemblyScripts {
+            load,
+            compile: "globalThis.__wasm_module = new WebAssembly.Module(__wasm_bytes);",
+            instantiate,
+            call: "__wasm_entry();",
+        }
+    }
+}
@@ -1 +1,90 @@
-pub struct SpiderMonkey {}
+use ::std::path::Path;
+
+use mozjs::jsapi::*;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rooted;
+use mozjs::rust::jsapi_wrapped as jsapi;
+use mozjs::rust::{transform_str_to_source_text, CompileOptionsWrapper};
+
+use crate::errors::TestError;
+use crate::js::spidermonkey::{script_error, Instance};
+use crate::validator::Validator;
+use crate::wasm::runner::{repeat, run, PhaseTimer, WebAssemblyScripts};
+use crate::wasm::{WasmEngine, WasmRunner};
+use crate::{Engine, TestResult};
+
+/// Wasm through the `WebAssembly` API of SpiderMonkey, the baseline compiler runs the module
+/// while Ion optimizes it in the background
+pub struct SpiderMonkey;
+
+impl SpiderMonkey {
+    pub fn new() -> Self {
+        SpiderMonkey
+    }
+}
+
+impl Default for SpiderMonkey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmRunner for SpiderMonkey {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> ::core::result::Result<TestResult, TestError> {
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::SpiderMonkey),
+            |(module, runs)| {
+                let scripts = WebAssemblyScripts::new(&module);
+
+                let mut timer = PhaseTimer::start();
+
+                let instance = Instance::new();
+                let rt = &instance.rt;
+                timer.init();
+
+                rooted!(in(rt.cx()) let global = instance.new_global());
+                let _ac = JSAutoRealm::new(rt.cx(), global.get());
+
+                rooted!(in(rt.cx()) let mut rval = UndefinedValue());
+
+                let mut evaluate = |code: &str, name: &str| {
+                    rt.evaluate_script(global.handle(), code, name, 1, rval.handle_mut())
+                        .map_err(|_| unsafe { script_error(rt.cx()) })
+                };
+
+                evaluate(&scripts.load, "load.js")?;
+                timer.context();
+
+                evaluate(scripts.compile, "compile.js")?;
+                timer.compile();
+
+                evaluate(&scripts.instantiate, "instantiate.js")?;
+
+                rooted!(in(rt.cx()) let call = unsafe {
+                    let options = CompileOptionsWrapper::new(rt.cx(), "call.js", 1);
+                    Compile1(rt.cx(), options.ptr, &mut transform_str_to_source_text(scripts.call))
+                });
+                if call.is_null() {
+                    return Err(unsafe { script_error(rt.cx()) }.into());
+                }
+                timer.instantiate();
+
+                repeat(runs, timer, || unsafe {
+                    if jsapi::JS_ExecuteScript(rt.cx(), call.handle(), rval.handle_mut()) {
+                        Ok(())
+                    } else {
+                        Err(script_error(rt.cx()))
+                    }
+                })
+            },
+        )
+    }
+}
@@ -1 +1,84 @@
-pub struct V8 {}
+use std::path::Path;
+
+use v8::{Context, ContextScope, HandleScope, Isolate, TryCatch};
+
+use crate::errors::{ScriptError, TestError};
+use crate::js::v8::V8 as JsV8;
+use crate::validator::Validator;
+use crate::wasm::runner::{repeat, run, PhaseTimer, WebAssemblyScripts};
+use crate::wasm::{WasmEngine, WasmRunner};
+use crate::{Engine, TestResult};
+
+/// Wasm through the `WebAssembly` API of V8, Liftoff compiles the module and TurboFan tiers up hot
+/// functions in the background while it runs
+pub struct V8;
+
+impl V8 {
+    pub fn new() -> Self {
+        V8
+    }
+
+    fn evaluate(tc: &mut TryCatch<HandleScope>, code: &str) -> Result<(), ScriptError> {
+        let code = v8::String::new(tc, code).unwrap();
+
+        match v8::Script::compile(tc, code, None).and_then(|script| script.run(tc)) {
+            Some(_) => Ok(()),
+            None => Err(JsV8::script_error(tc)),
+        }
+    }
+}
+
+impl Default for V8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmRunner for V8 {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::V8),
+            |(module, runs)| {
+                let scripts = WebAssemblyScripts::new(&module);
+                JsV8::initialize();
+
+                let mut timer = PhaseTimer::start();
+
+                let isolate = &mut Isolate::new(Default::default());
+                timer.init();
+
+                let hs = &mut HandleScope::new(isolate);
+                let c = Context::new(hs);
+                let s = &mut ContextScope::new(hs, c);
+                let tc = &mut TryCatch::new(s);
+
+                Self::evaluate(tc, &scripts.load)?;
+                timer.context();
+
+                Self::evaluate(tc, scripts.compile)?;
+                timer.compile();
+
+                Self::evaluate(tc, &scripts.instantiate)?;
+
+                let code = v8::String::new(tc, scripts.call).unwrap();
+                let Some(call) = v8::Script::compile(tc, code, None) else {
+                    return Err(JsV8::script_error(tc).into());
+                };
+                timer.instantiate();
+
+                repeat(runs, timer, || match call.run(tc) {
+                    Some(_) => Ok(()),
+                    None => Err(JsV8::script_error(tc)),
+                })
+            },
+        )
+    }
+}
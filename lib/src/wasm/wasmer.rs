@@ -1 +1,190 @@
-pub struct Wasmer {}
+use std::io::Read;
+use std::path::Path;
+
+use wasmer::{
+    Cranelift, ImportObject, Instance, InstantiationError, Module, RuntimeError, Singlepass, Store,
+    Universal,
+};
+use wasmer_wasi::{Pipe, WasiEnv, WasiError, WasiState, WasiStateCreationError};
+
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::validator::Validator;
+use crate::wasm::runner::{
+    error, no_export, repeat, run, ChildError, ChildResult, PhaseTimer, Runs, Wasi, WasmModule,
+};
+use crate::wasm::{WasmEngine, WasmRunner, WasmerCompiler};
+use crate::{Engine, TestResult};
+
+/// Wasmer with one of its compiler backends, the module is compiled ahead of time when it is
+/// loaded, so the compile phase is where the backends differ the most.
+///
+/// `fuel` from the manifest is ignored, Wasmer only meters modules compiled with its metering
+/// middleware, which would change the code that is measured.
+pub struct Wasmer {
+    compiler: WasmerCompiler,
+}
+
+impl Wasmer {
+    pub fn new(compiler: WasmerCompiler) -> Result<Self, TestError> {
+        if compiler == WasmerCompiler::LLVM && !cfg!(feature = "wasmer-llvm") {
+            return Err(TestError::FeatureNotEnabled("wasmer-llvm"));
+        }
+
+        Ok(Wasmer { compiler })
+    }
+
+    fn store(compiler: WasmerCompiler) -> Store {
+        match compiler {
+            WasmerCompiler::Singlepass => {
+                Store::new(&Universal::new(Singlepass::default()).engine())
+            }
+            WasmerCompiler::Cranelift => Store::new(&Universal::new(Cranelift::default()).engine()),
+            #[cfg(feature = "wasmer-llvm")]
+            WasmerCompiler::LLVM => Store::new(&Universal::new(wasmer::LLVM::default()).engine()),
+            #[cfg(not(feature = "wasmer-llvm"))]
+            WasmerCompiler::LLVM => unreachable!("Wasmer::new rejects LLVM without wasmer-llvm"),
+        }
+    }
+
+    fn run_module(compiler: WasmerCompiler, module: WasmModule, runs: Runs) -> ChildResult {
+        let mut timer = PhaseTimer::start();
+
+        let store = Self::store(compiler);
+        timer.init();
+
+        let mut wasi = match &module.wasi {
+            Some(wasi) => Some(Self::wasi_env(wasi).map_err(Self::engine_error)?),
+            None => None,
+        };
+        timer.context();
+
+        let compiled = Module::new(&store, &module.bytes).map_err(error)?;
+        timer.compile();
+
+        let imports = match &mut wasi {
+            Some(wasi) => wasi.import_object(&compiled).map_err(error)?,
+            None => ImportObject::new(),
+        };
+
+        let instance = Instance::new(&compiled, &imports).map_err(Self::instantiation_error)?;
+
+        let func = instance
+            .exports
+            .get_function(&module.entry)
+            .map_err(|_| no_export(&module.entry))?;
+        timer.instantiate();
+
+        repeat(runs, timer, || {
+            let res = func.call(&[]);
+
+            if let Some(wasi) = &wasi {
+                console::write(&Self::take_stdout(wasi));
+            }
+
+            match res {
+                Ok(_) => Ok(()),
+                Err(err) => match err.downcast::<WasiError>() {
+                    // `proc_exit(0)` ends a WASI command that succeeded
+                    Ok(WasiError::Exit(0)) => Ok(()),
+                    Ok(err) => Err(error(err)),
+                    Err(err) => Err(Self::runtime_error(err)),
+                },
+            }
+        })
+    }
+
+    /// WASI with `wasi.dir` preopened as `.`, stdout is written to a pipe
+    fn wasi_env(wasi: &Wasi) -> Result<WasiEnv, WasiStateCreationError> {
+        let (program, args) = wasi
+            .args
+            .split_first()
+            .expect("argv starts with the module");
+
+        WasiState::new(program)
+            .args(args)
+            .map_dir(".", &wasi.dir)?
+            .stdout(Box::new(Pipe::new()))
+            .finalize()
+    }
+
+    /// Everything the module wrote to stdout since the last call
+    fn take_stdout(wasi: &WasiEnv) -> String {
+        let mut out = String::new();
+        let mut state = wasi.state();
+
+        if let Ok(Some(stdout)) = state.fs.stdout_mut() {
+            stdout.read_to_string(&mut out).ok();
+        }
+
+        out
+    }
+
+    /// Building the WASI environment failed, that's no fault of the module
+    fn engine_error(err: WasiStateCreationError) -> ChildError {
+        ChildError::Engine(format!("Wasmer failed: {}", err))
+    }
+
+    fn instantiation_error(err: InstantiationError) -> ChildError {
+        match err {
+            // a trap in the start function
+            InstantiationError::Start(err) => Self::runtime_error(err),
+            err => error(err),
+        }
+    }
+
+    fn runtime_error(err: RuntimeError) -> ChildError {
+        let stack = err
+            .trace()
+            .iter()
+            .map(|frame| {
+                format!(
+                    "    at {}!{} (offset {:#x})",
+                    frame.module_name(),
+                    frame
+                        .function_name()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("<wasm function {}>", frame.func_index())),
+                    frame.module_offset()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        ChildError::Script(ScriptError {
+            message: err.message(),
+            stack: (!stack.is_empty()).then_some(stack),
+            ..Default::default()
+        })
+    }
+}
+
+impl WasmRunner for Wasmer {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        // the child is a plain fn, so the backend is picked by which closure is sent
+        let run_module: fn((WasmModule, Runs)) -> ChildResult = match self.compiler {
+            WasmerCompiler::Singlepass => {
+                |(module, runs)| Self::run_module(WasmerCompiler::Singlepass, module, runs)
+            }
+            WasmerCompiler::Cranelift => {
+                |(module, runs)| Self::run_module(WasmerCompiler::Cranelift, module, runs)
+            }
+            WasmerCompiler::LLVM => {
+                |(module, runs)| Self::run_module(WasmerCompiler::LLVM, module, runs)
+            }
+        };
+
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::Wasmer(self.compiler)),
+            run_module,
+        )
+    }
+}
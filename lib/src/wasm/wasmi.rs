@@ -1 +1,77 @@
-pub struct Wasmer {}
+use std::path::Path;
+
+use wasmi::{Config, Linker, Module, Store, Val};
+
+use crate::errors::TestError;
+use crate::validator::Validator;
+use crate::wasm::runner::{error, no_export, repeat, run, PhaseTimer};
+use crate::wasm::{WasmEngine, WasmRunner};
+use crate::{Engine, TestResult};
+
+/// Wasmi, an interpreter that never generates machine code, the baseline for the JITs and the
+/// option for platforms that forbid executable memory.
+///
+/// Modules are translated to Wasmi bytecode eagerly, so the compile phase is comparable to the
+/// JITs. `fuel` from the manifest is consumed by every executed instruction.
+pub struct Wasmi;
+
+impl Wasmi {
+    pub fn new() -> Self {
+        Wasmi
+    }
+}
+
+impl Default for Wasmi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmRunner for Wasmi {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::Wasmi),
+            |(module, runs)| {
+                let mut timer = PhaseTimer::start();
+
+                let mut config = Config::default();
+                config.consume_fuel(module.fuel.is_some());
+
+                let engine = wasmi::Engine::new(&config);
+                let mut store = Store::new(&engine, ());
+
+                if let Some(fuel) = module.fuel {
+                    store.set_fuel(fuel).map_err(error)?;
+                }
+                timer.init();
+
+                let compiled = Module::new(&engine, &module.bytes).map_err(error)?;
+                timer.compile();
+
+                let instance = Linker::<()>::new(&engine)
+                    .instantiate(&mut store, &compiled)
+                    .and_then(|pre| pre.start(&mut store))
+                    .map_err(error)?;
+
+                let func = instance
+                    .get_func(&store, &module.entry)
+                    .ok_or_else(|| no_export(&module.entry))?;
+
+                let mut results = vec![Val::I32(0); func.ty(&store).results().len()];
+                timer.instantiate();
+
+                repeat(runs, timer, || {
+                    func.call(&mut store, &[], &mut results).map_err(error)
+                })
+            },
+        )
+    }
+}
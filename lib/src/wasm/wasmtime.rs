@@ -1 +1,157 @@
-pub struct Wasmer {}
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use wasmtime::{Config, Linker, Module, Store, Trap, Val, WasmBacktrace};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+use crate::console;
+use crate::errors::{ScriptError, TestError};
+use crate::validator::Validator;
+use crate::wasm::runner::{no_export, repeat, run, ChildError, PhaseTimer, Wasi};
+use crate::wasm::{WasmEngine, WasmRunner};
+use crate::{Engine, TestResult};
+
+/// How often the epoch is incremented, the granularity in which modules are interrupted
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+pub struct Wasmtime;
+
+impl Wasmtime {
+    pub fn new() -> Self {
+        Wasmtime
+    }
+
+    fn error(err: wasmtime::Error) -> ChildError {
+        let message = match err.downcast_ref::<Trap>() {
+            Some(Trap::Interrupt) => return ChildError::Timeout,
+            // the top level error of a trap is only the backtrace
+            Some(trap) => trap.to_string(),
+            None => match err.downcast_ref::<I32Exit>() {
+                Some(I32Exit(code)) => format!("Module exited with code {}", code),
+                None => format!("{:#}", err),
+            },
+        };
+
+        ChildError::Script(ScriptError {
+            message,
+            stack: err
+                .downcast_ref::<WasmBacktrace>()
+                .map(|backtrace| backtrace.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// Setting up the engine, its WASI context or linker failed, that's no fault of the module
+    fn engine_error(err: wasmtime::Error) -> ChildError {
+        ChildError::Engine(format!("Wasmtime failed: {:#}", err))
+    }
+
+    /// WASI preview 1 with `wasi.dir` preopened as `.`, stdout is written to `stdout`
+    fn wasi_ctx(wasi: &Wasi, stdout: &MemoryOutputPipe) -> wasmtime::Result<WasiP1Ctx> {
+        Ok(WasiCtxBuilder::new()
+            .args(&wasi.args)
+            .stdout(stdout.clone())
+            .inherit_stderr()
+            .preopened_dir(&wasi.dir, ".", DirPerms::all(), FilePerms::all())?
+            .build_p1())
+    }
+}
+
+impl Default for Wasmtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmRunner for Wasmtime {
+    fn run_wasm<'a>(
+        &'a mut self,
+        path: &Path,
+        wasm: &[u8],
+        validator: &'a Validator,
+    ) -> Result<TestResult, TestError> {
+        run(
+            path,
+            wasm,
+            validator,
+            Engine::Wasm(WasmEngine::Wasmtime),
+            |(module, runs)| {
+                let started = Instant::now();
+                let mut timer = PhaseTimer::start();
+
+                let mut config = Config::new();
+                config.consume_fuel(module.fuel.is_some());
+                config.epoch_interruption(true);
+
+                let engine = wasmtime::Engine::new(&config).map_err(Self::engine_error)?;
+                timer.init();
+
+                let stdout = MemoryOutputPipe::new(usize::MAX);
+                let wasi = match &module.wasi {
+                    Some(wasi) => Some(Self::wasi_ctx(wasi, &stdout).map_err(Self::engine_error)?),
+                    None => None,
+                };
+
+                let mut linker = Linker::new(&engine);
+                if wasi.is_some() {
+                    preview1::add_to_linker_sync(&mut linker, |wasi: &mut Option<WasiP1Ctx>| {
+                        wasi.as_mut().expect("WASI is only linked with a context")
+                    })
+                    .map_err(Self::engine_error)?;
+                }
+
+                let mut store = Store::new(&engine, wasi);
+
+                if let Some(fuel) = module.fuel {
+                    store.set_fuel(fuel).map_err(Self::engine_error)?;
+                }
+                timer.context();
+
+                let compiled = Module::new(&engine, &module.bytes).map_err(Self::error)?;
+                timer.compile();
+
+                // a module that is stuck in its start function is interrupted as well
+                let deadline = runs.timeout.saturating_sub(started.elapsed());
+                store.set_epoch_deadline((deadline.as_millis() / EPOCH_TICK.as_millis()) as u64);
+                store.epoch_deadline_trap();
+
+                let ticker = engine.clone();
+                thread::spawn(move || loop {
+                    thread::sleep(EPOCH_TICK);
+                    ticker.increment_epoch();
+                });
+
+                let instance = linker
+                    .instantiate(&mut store, &compiled)
+                    .map_err(Self::error)?;
+
+                let func = instance
+                    .get_func(&mut store, &module.entry)
+                    .ok_or_else(|| no_export(&module.entry))?;
+
+                let mut results = vec![Val::I32(0); func.ty(&store).results().len()];
+                timer.instantiate();
+
+                let mut written = 0;
+
+                repeat(runs, timer, || {
+                    let res = func.call(&mut store, &[], &mut results);
+
+                    let out = stdout.contents();
+                    console::write(&String::from_utf8_lossy(&out[written..]));
+                    written = out.len();
+
+                    match res {
+                        Ok(()) => Ok(()),
+                        // `proc_exit(0)` ends a WASI command that succeeded
+                        Err(err) if matches!(err.downcast_ref(), Some(I32Exit(0))) => Ok(()),
+                        Err(err) => Err(Self::error(err)),
+                    }
+                })
+            },
+        )
+    }
+}
@@ -0,0 +1,243 @@
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::TestError;
+use crate::js::external::ExternalEngine;
+use crate::{test_with, EngineInfo, RunOptions, Test, TestResult, JS_ENGINES, WASM_ENGINES};
+
+/// Starts every line of the worker that is a message, anything else on stdout comes from an engine
+const MESSAGE_PREFIX: &str = "@speed-test-worker ";
+
+/// # Worker
+/// `mozjs` and `v8` can't be compiled into the same binary, so engines behind a feature this
+/// binary was built without are run by a worker binary that was built with it.
+///
+/// The orchestrator starts `<worker> worker <request as JSON>`, the worker answers with one JSON
+/// message per line on stdout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerRequest {
+    pub engines: Vec<String>,
+    pub paths: Vec<PathBuf>,
+    pub options: RunOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum WorkerMessage {
+    Result(Box<TestResult>),
+    Error(String),
+}
+
+/// Runs the tests under `paths` with `engines`, engines behind a disabled feature are run by
+/// their worker. No `engines` means every engine that is compiled in or has a worker, and every
+/// `external` engine.
+pub fn run_engines(
+    paths: &[PathBuf],
+    engines: &[String],
+    external: &[ExternalEngine],
+    options: &RunOptions,
+) -> Result<Vec<TestResult>, TestError> {
+    let all = engines.is_empty();
+
+    let selected = external
+        .iter()
+        .filter(|engine| all || engines.iter().any(|name| engine.is(name)))
+        .collect::<Vec<_>>();
+
+    // the names left are built-in engines
+    let engines = engines
+        .iter()
+        .filter(|name| !external.iter().any(|engine| engine.is(name)))
+        .collect::<Vec<_>>();
+
+    let engines = if all {
+        JS_ENGINES
+            .iter()
+            .chain(WASM_ENGINES)
+            .filter(|engine| engine.enabled || engine.feature.and_then(worker_executable).is_some())
+            .collect::<Vec<_>>()
+    } else {
+        engines
+            .iter()
+            .map(|name| {
+                engine_info(name)
+                    .ok_or_else(|| TestError::String(format!("Unknown engine {}", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut results = vec![];
+
+    // local engines first, their children return from `main` before any worker is started
+    let local = engines
+        .iter()
+        .filter(|engine| engine.enabled)
+        .map(|engine| engine.name)
+        .collect::<Vec<_>>();
+    let test = Test::new();
+    let mut runners = test.runners(&local)?;
+
+    for engine in selected {
+        runners.js.push(test.external(engine)?);
+    }
+
+    if !runners.is_empty() {
+        for path in paths {
+            results.append(&mut test_with(path, &mut runners, options)?);
+        }
+    }
+
+    let mut features = vec![];
+    for engine in &engines {
+        if let (false, Some(feature)) = (engine.enabled, engine.feature) {
+            if !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+    }
+
+    for feature in features {
+        let executable = worker_executable(feature).ok_or_else(|| {
+            TestError::String(format!(
+                "No worker for the `{}` feature, build one or set SPEED_TEST_WORKER_{}",
+                feature,
+                feature.to_uppercase()
+            ))
+        })?;
+
+        let request = WorkerRequest {
+            engines: engines
+                .iter()
+                .filter(|engine| !engine.enabled && engine.feature == Some(feature))
+                .map(|engine| engine.name.to_string())
+                .collect(),
+            paths: paths.to_vec(),
+            options: options.clone(),
+        };
+
+        results.append(&mut run_in_worker(&executable, &request)?);
+    }
+
+    // keep the engines of a test next to each other
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(results)
+}
+
+fn engine_info(name: &str) -> Option<&'static EngineInfo> {
+    JS_ENGINES
+        .iter()
+        .chain(WASM_ENGINES)
+        .find(|engine| engine.is(name))
+}
+
+/// The worker for engines behind `feature`, `$SPEED_TEST_WORKER_<FEATURE>` or
+/// `<this executable>-<feature>` next to this executable
+pub fn worker_executable(feature: &str) -> Option<PathBuf> {
+    if let Some(path) = env::var_os(format!("SPEED_TEST_WORKER_{}", feature.to_uppercase())) {
+        return Some(PathBuf::from(path));
+    }
+
+    let exe = env::current_exe().ok()?;
+    let name = format!("{}-{}", exe.file_stem()?.to_str()?, feature);
+    let worker = exe.with_file_name(name);
+
+    worker.is_file().then_some(worker)
+}
+
+/// Orchestrator side, runs `request` in the worker at `executable`
+pub fn run_in_worker(
+    executable: &Path,
+    request: &WorkerRequest,
+) -> Result<Vec<TestResult>, TestError> {
+    let request = serde_json::to_string(request).map_err(|err| TestError::Other(Box::new(err)))?;
+
+    let mut child = Command::new(executable)
+        .arg("worker")
+        .arg(request)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("stdout of the worker is piped");
+
+    let mut results = vec![];
+    let mut error = None;
+
+    for line in BufReader::new(stdout).lines() {
+        let line = line?;
+
+        let Some(idx) = line.find(MESSAGE_PREFIX) else {
+            // not ours, keep it away from our own stdout, which may be JSON
+            eprintln!("{}", line);
+            continue;
+        };
+
+        let message = serde_json::from_str(&line[idx + MESSAGE_PREFIX.len()..])
+            .map_err(|err| TestError::Other(Box::new(err)))?;
+
+        match message {
+            WorkerMessage::Result(res) => results.push(*res),
+            WorkerMessage::Error(err) => error = Some(err),
+        }
+    }
+
+    let status = child.wait()?;
+
+    if let Some(err) = error {
+        return Err(TestError::String(format!(
+            "Worker {}: {}",
+            executable.display(),
+            err
+        )));
+    }
+
+    if !status.success() {
+        return Err(TestError::String(format!(
+            "Worker {} failed with {}",
+            executable.display(),
+            status
+        )));
+    }
+
+    Ok(results)
+}
+
+/// Worker side, runs `request` and reports every result on stdout
+pub fn serve(request: &str) -> Result<(), TestError> {
+    let request: WorkerRequest =
+        serde_json::from_str(request).map_err(|err| TestError::Other(Box::new(err)))?;
+
+    let mut stdout = std::io::stdout();
+    let res = serve_request(&request, &mut stdout);
+
+    if let Err(err) = &res {
+        send(&mut stdout, &WorkerMessage::Error(err.to_string()))?;
+    }
+
+    res
+}
+
+fn serve_request(request: &WorkerRequest, out: &mut impl Write) -> Result<(), TestError> {
+    let mut runners = Test::new().runners(&request.engines)?;
+
+    for path in &request.paths {
+        for res in test_with(path, &mut runners, &request.options)? {
+            send(out, &WorkerMessage::Result(Box::new(res)))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn send(out: &mut impl Write, message: &WorkerMessage) -> Result<(), TestError> {
+    let message = serde_json::to_string(message).map_err(|err| TestError::Other(Box::new(err)))?;
+
+    writeln!(out, "{}{}", MESSAGE_PREFIX, message)?;
+    out.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use lib::export::{format_ns, write_aligned};
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+use crate::read_results;
+
+/// Prints the median of every test and engine in `a` next to the one in `b`
+pub(crate) fn compare(a: &Path, b: &Path) -> Result<(), Box<dyn Error>> {
+    let a = read_results(a)?;
+    let b = read_results(b)?;
+
+    let mut rows = vec![];
+
+    for old in &a {
+        let Some(new) = b
+            .iter()
+            .find(|new| new.path == old.path && new.engine == old.engine)
+        else {
+            continue;
+        };
+
+        let old_median = old.stats.as_ref().map(|s| s.median);
+        let new_median = new.stats.as_ref().map(|s| s.median);
+
+        let change = match (old_median, new_median) {
+            (Some(old), Some(new)) if old > 0.0 => format!("{:+.1}%", (new - old) / old * 100.0),
+            _ => "-".to_string(),
+        };
+
+        rows.push(vec![
+            old.path.display().to_string(),
+            old.engine.to_string(),
+            old_median.map_or("-".to_string(), format_ns),
+            new_median.map_or("-".to_string(), format_ns),
+            change,
+            format!("{} -> {}", old.outcome.kind(), new.outcome.kind()),
+        ]);
+    }
+
+    write_aligned(
+        &["test", "engine", "a", "b", "change", "outcome"],
+        &rows,
+        io::stdout(),
+    )?;
+
+    Ok(())
+}
@@ -1,35 +1,267 @@
-use lib::validator::Validator;
-use std::env;
+use clap::{Parser, Subcommand, ValueEnum};
+use lib::export::{read_json, write_csv, write_json, write_table};
+use lib::js::external::ExternalEngine;
+use lib::worker::{run_engines, serve, worker_executable};
+use lib::{RunOptions, TestResult, JS_ENGINES, WASM_ENGINES};
 use std::error::Error;
-use std::path::Path;
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let path = env::args().nth(1).unwrap();
-    let engine = env::args().nth(2).unwrap();
-    let path = Path::new(&path);
-
-    let test = lib::Test::new();
-
-    if path.extension().unwrap().eq("js") {
-        let mut engine = match engine.to_lowercase().as_str() {
-            "duktape" | "dt" => test.duktape()?,
-            "javascriptcore" | "jsc" => test.javascriptcore()?,
-            "v8" => test.v8()?,
-            "deno" => test.deno()?,
-            "spidermonkey" | "sm" | "mozjs" => test.spidermonkey()?,
-            _ => return Err(Box::from("Unknown engine")),
-        };
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
-        let mut validator = Validator::new();
+mod compare;
+mod pairs;
+mod report;
 
-        validator.reruns = 100;
+#[derive(Debug, Parser)]
+#[command(about = "Compares the speed of JS and WASM engines")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-        let res = engine.run_js_file(path, &validator)?;
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Runs scripts or directories of scripts with the selected engines
+    Run {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Engines to run, all compiled in engines if not given
+        #[arg(long, value_delimiter = ',')]
+        engine: Vec<String>,
+        /// TOML file with engines that are run through their command line, see
+        /// `lib::js::external`
+        #[arg(long)]
+        external: Option<PathBuf>,
+        /// Measured runs per test, overrides the manifests
+        #[arg(long)]
+        reruns: Option<u32>,
+        /// Unmeasured runs before the measured ones, overrides the manifests
+        #[arg(long)]
+        warmup: Option<u32>,
+        /// Timeout in seconds for tests whose manifest doesn't set one
+        #[arg(long, value_parser = parse_timeout)]
+        timeout: Option<Duration>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+        /// Writes the results to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Runs every `foo.js` against the `foo.wasm` or `foo.wat` next to it and reports the JS/wasm
+    /// ratio per engine
+    Pairs {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Engines to run, all compiled in engines if not given
+        #[arg(long, value_delimiter = ',')]
+        engine: Vec<String>,
+        /// TOML file with engines that are run through their command line, see
+        /// `lib::js::external`
+        #[arg(long)]
+        external: Option<PathBuf>,
+        /// Measured runs per test, overrides the manifests
+        #[arg(long)]
+        reruns: Option<u32>,
+        /// Unmeasured runs before the measured ones, overrides the manifests
+        #[arg(long)]
+        warmup: Option<u32>,
+        /// Timeout in seconds for tests whose manifest doesn't set one
+        #[arg(long, value_parser = parse_timeout)]
+        timeout: Option<Duration>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+        /// Writes the ratios to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Lists the engines and whether they are compiled in
+    ListEngines,
+    /// Compares the medians of two JSON result files
+    Compare { a: PathBuf, b: PathBuf },
+    /// Summarizes JSON result files
+    Report {
+        #[arg(required = true)]
+        results: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Runs engines for an orchestrator built without them, see `lib::worker`
+    #[command(hide = true)]
+    Worker { request: String },
+}
 
-        println!("{:?}", res);
-    } else {
-        return Err(Box::from("Not a JS file; WASM not supported yet"));
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+    Table,
+}
+
+fn main() -> ExitCode {
+    // engine children start here as well and must not get any further
+    procspawn::init();
+
+    let cli = Cli::parse();
+
+    let res = match cli.command {
+        Command::Run {
+            paths,
+            engine,
+            external,
+            reruns,
+            warmup,
+            timeout,
+            format,
+            output,
+        } => {
+            let options = RunOptions {
+                reruns,
+                warmup,
+                timeout,
+            };
+
+            load_external(external.as_deref()).and_then(|external| {
+                run(
+                    &paths,
+                    &engine,
+                    &external,
+                    &options,
+                    format,
+                    output.as_deref(),
+                )
+            })
+        }
+        Command::Pairs {
+            paths,
+            engine,
+            external,
+            reruns,
+            warmup,
+            timeout,
+            format,
+            output,
+        } => {
+            let options = RunOptions {
+                reruns,
+                warmup,
+                timeout,
+            };
+
+            load_external(external.as_deref()).and_then(|external| {
+                pairs::pairs(
+                    &paths,
+                    &engine,
+                    &external,
+                    &options,
+                    format,
+                    output.as_deref(),
+                )
+            })
+        }
+        Command::ListEngines => {
+            list_engines();
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Compare { a, b } => compare::compare(&a, &b).map(|_| ExitCode::SUCCESS),
+        Command::Report { results, format } => report::report(&results, format),
+        Command::Worker { request } => serve(&request)
+            .map(|_| ExitCode::SUCCESS)
+            .map_err(Box::from),
+    };
+
+    match res {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(
+    paths: &[PathBuf],
+    engines: &[String],
+    external: &[ExternalEngine],
+    options: &RunOptions,
+    format: Format,
+    output: Option<&Path>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let results = run_engines(paths, engines, external, options)?;
+
+    match output {
+        Some(path) => write_results(&results, format, File::create(path)?)?,
+        None => write_results(&results, format, io::stdout())?,
+    }
+
+    Ok(exit_code(&results))
+}
+
+fn load_external(path: Option<&Path>) -> Result<Vec<ExternalEngine>, Box<dyn Error>> {
+    match path {
+        Some(path) => Ok(ExternalEngine::load(path)?),
+        None => Ok(vec![]),
+    }
+}
+
+fn parse_timeout(secs: &str) -> Result<Duration, String> {
+    match secs.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(Duration::from_secs_f64(secs)),
+        _ => Err("expected a positive number of seconds".to_string()),
+    }
+}
+
+fn list_engines() {
+    for engine in JS_ENGINES.iter().chain(WASM_ENGINES) {
+        let aliases = if engine.aliases.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", engine.aliases.join(", "))
+        };
+
+        let state = match (engine.enabled, engine.feature) {
+            (true, _) => "enabled".to_string(),
+            (false, Some(feature)) => match worker_executable(feature) {
+                Some(worker) => format!("runs in the worker {}", worker.display()),
+                None => format!(
+                    "disabled, enable the `{}` feature or build a worker with it",
+                    feature
+                ),
+            },
+            (false, None) => "disabled".to_string(),
+        };
+
+        println!("{}{}: {}", engine.name, aliases, state);
+    }
+}
+
+pub(crate) fn write_results(
+    results: &[TestResult],
+    format: Format,
+    writer: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => write_json(results, writer)?,
+        Format::Csv => write_csv(results, writer)?,
+        Format::Table => write_table(results, writer)?,
     }
 
     Ok(())
 }
+
+pub(crate) fn read_results(path: &Path) -> Result<Vec<TestResult>, Box<dyn Error>> {
+    let file = File::open(path)?;
+
+    Ok(read_json(BufReader::new(file))?)
+}
+
+/// Fails if any test didn't pass, so CI can gate on it
+pub(crate) fn exit_code(results: &[TestResult]) -> ExitCode {
+    if results.iter().all(|res| res.success) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
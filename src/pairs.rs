@@ -0,0 +1,64 @@
+use lib::export::{write_pairs_csv, write_pairs_json, write_pairs_table};
+use lib::js::external::ExternalEngine;
+use lib::pairs::{find_pairs, pair_results, Pair, PairResult};
+use lib::worker::run_engines;
+use lib::RunOptions;
+use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use crate::Format;
+
+/// Runs the JS/wasm pairs under `paths` and writes the JS/wasm ratio of every engine
+pub(crate) fn pairs(
+    paths: &[PathBuf],
+    engines: &[String],
+    external: &[ExternalEngine],
+    options: &RunOptions,
+    format: Format,
+    output: Option<&Path>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut pairs = vec![];
+    for path in paths {
+        pairs.append(&mut find_pairs(path)?);
+    }
+
+    if pairs.is_empty() {
+        return Err("No `.js` file with a `.wasm` or `.wat` file of the same name".into());
+    }
+
+    let files = pairs.iter().flat_map(Pair::paths).collect::<Vec<_>>();
+    let results = run_engines(&files, engines, external, options)?;
+    let pairs = pair_results(&results);
+
+    match output {
+        Some(path) => write_pairs(&pairs, format, File::create(path)?)?,
+        None => write_pairs(&pairs, format, io::stdout())?,
+    }
+
+    let success = results.iter().all(|res| res.success)
+        && pairs.iter().all(|pair| pair.console_valid != Some(false));
+
+    if success {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn write_pairs(
+    pairs: &[PairResult],
+    format: Format,
+    writer: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => write_pairs_json(pairs, writer)?,
+        Format::Csv => write_pairs_csv(pairs, writer)?,
+        Format::Table => write_pairs_table(pairs, writer)?,
+    }
+
+    Ok(())
+}
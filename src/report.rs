@@ -0,0 +1,49 @@
+use lib::export::write_aligned;
+use std::error::Error;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use crate::{exit_code, read_results, write_results, Format};
+
+/// Renders saved results again, tables get a per engine summary below
+pub(crate) fn report(paths: &[PathBuf], format: Format) -> Result<ExitCode, Box<dyn Error>> {
+    let mut results = vec![];
+    for path in paths {
+        results.append(&mut read_results(path)?);
+    }
+
+    write_results(&results, format, io::stdout())?;
+
+    if let Format::Table = format {
+        let mut engines = vec![];
+        for res in &results {
+            if !engines.contains(&res.engine) {
+                engines.push(res.engine.clone());
+            }
+        }
+
+        let rows = engines
+            .into_iter()
+            .map(|engine| {
+                let results = results
+                    .iter()
+                    .filter(|res| res.engine == engine)
+                    .collect::<Vec<_>>();
+
+                let passed = results.iter().filter(|res| res.success).count();
+
+                vec![
+                    engine.to_string(),
+                    passed.to_string(),
+                    (results.len() - passed).to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        println!();
+        write_aligned(&["engine", "passed", "failed"], &rows, io::stdout())?;
+    }
+
+    Ok(exit_code(&results))
+}